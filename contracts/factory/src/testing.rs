@@ -1,6 +1,8 @@
+use std::str::FromStr;
+
 use cosmwasm_std::{
-    attr, from_binary, to_binary, Addr, Reply, ReplyOn, SubMsg, SubMsgResponse, SubMsgResult,
-    WasmMsg,
+    attr, from_binary, to_binary, Addr, Reply, ReplyOn, StdError, SubMsg, SubMsgResponse,
+    SubMsgResult, WasmMsg,
 };
 
 use crate::mock_querier::mock_dependencies;
@@ -25,6 +27,35 @@ use protobuf::Message;
 fn pair_type_to_string() {
     assert_eq!(PairType::Xyk {}.to_string(), "xyk");
     assert_eq!(PairType::Stable {}.to_string(), "stable");
+    assert_eq!(
+        PairType::Custom("concentrated".to_string()).to_string(),
+        "custom-concentrated"
+    );
+}
+
+#[test]
+fn pair_type_from_string() {
+    for pair_type in [
+        PairType::Xyk {},
+        PairType::Stable {},
+        PairType::Custom("concentrated".to_string()),
+        PairType::Custom("Bonded".to_string()),
+    ] {
+        assert_eq!(
+            PairType::from_str(&pair_type.to_string()).unwrap(),
+            pair_type
+        );
+    }
+
+    // Names are case sensitive
+    assert_eq!(
+        PairType::from_str("Xyk").unwrap_err(),
+        StdError::generic_err("Unknown pair type: Xyk")
+    );
+    assert_eq!(
+        PairType::from_str("concentrated").unwrap_err(),
+        StdError::generic_err("Unknown pair type: concentrated")
+    );
 }
 
 #[test]
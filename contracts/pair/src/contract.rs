@@ -1,28 +1,29 @@
 use crate::error::ContractError;
-use crate::state::{Config, BALANCES, CONFIG};
+use crate::state::{Config, BALANCES, CONFIG, CUMULATIVE_FEES};
 use std::convert::TryInto;
 
 use cosmwasm_std::{
     attr, entry_point, from_binary, to_binary, Addr, Binary, CosmosMsg, Decimal, Decimal256, Deps,
-    DepsMut, Env, Fraction, MessageInfo, QuerierWrapper, Reply, ReplyOn, Response, StdError,
+    DepsMut, Env, Fraction, MessageInfo, Order, QuerierWrapper, Reply, ReplyOn, Response, StdError,
     StdResult, SubMsg, Uint128, Uint256, Uint64, WasmMsg,
 };
 
 use crate::response::MsgInstantiateContractResponse;
 use astroport::asset::{
     addr_opt_validate, check_swap_parameters, format_lp_token_name, Asset, AssetInfo, CoinsExt,
-    PairInfo, MINIMUM_LIQUIDITY_AMOUNT,
+    Decimal256Ext, PairInfo, MINIMUM_LIQUIDITY_AMOUNT,
 };
 use astroport::decimal2decimal256;
 use astroport::factory::PairType;
 use astroport::generator::Cw20HookMsg as GeneratorHookMsg;
 use astroport::pair::{
-    migration_check, ConfigResponse, XYKPoolConfig, XYKPoolParams, XYKPoolUpdateParams,
-    DEFAULT_SLIPPAGE, MAX_ALLOWED_SLIPPAGE,
+    execution_price, migration_check, slippage_attributes, xyk_impermanent_loss, ConfigResponse,
+    XYKPoolConfig, XYKPoolParams, XYKPoolUpdateParams, DEFAULT_SLIPPAGE, MAX_ALLOWED_SLIPPAGE,
 };
 use astroport::pair::{
     CumulativePricesResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, PoolResponse,
-    QueryMsg, ReverseSimulationResponse, SimulationResponse, TWAP_PRECISION,
+    QueryMsg, ReverseSimulationResponse, SimulationResponse, SwapResponseData,
+    TrackedHeightRangeResponse, TWAP_PRECISION,
 };
 use astroport::querier::{query_factory_config, query_fee_info, query_supply};
 use astroport::{token::InstantiateMsg as TokenInstantiateMsg, U256};
@@ -59,10 +60,13 @@ pub fn instantiate(
     }
 
     let mut track_asset_balances = false;
+    let mut min_commission = vec![];
 
     if let Some(init_params) = msg.init_params {
         let params: XYKPoolParams = from_binary(&init_params)?;
         track_asset_balances = params.track_asset_balances.unwrap_or_default();
+        min_commission = params.min_commission.unwrap_or_default();
+        check_min_commission(&msg.asset_infos, &min_commission)?;
     }
 
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
@@ -79,6 +83,7 @@ pub fn instantiate(
         price0_cumulative_last: Uint128::zero(),
         price1_cumulative_last: Uint128::zero(),
         track_asset_balances,
+        min_commission,
     };
 
     if track_asset_balances {
@@ -135,7 +140,7 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
             let mut config: Config = CONFIG.load(deps.storage)?;
 
             if config.pair_info.liquidity_token != Addr::unchecked("") {
-                return Err(ContractError::Unauthorized {});
+                return Err(ContractError::LpTokenAlreadySet {});
             }
 
             let data = msg.result.unwrap().data.unwrap();
@@ -159,7 +164,7 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
 /// Exposes all the execute functions available in the contract.
 ///
 /// ## Variants
-/// * **ExecuteMsg::UpdateConfig { params: Binary }** Not supported.
+/// * **ExecuteMsg::UpdateConfig { params: Binary }** Updates the pool parameters, see [`XYKPoolUpdateParams`].
 ///
 /// * **ExecuteMsg::Receive(msg)** Receives a message of type [`Cw20ReceiveMsg`] and processes
 /// it depending on the received template.
@@ -176,6 +181,7 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
 ///             belief_price,
 ///             max_spread,
 ///             to,
+///             refund_excess,
 ///         }** Performs a swap operation with the specified parameters.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
@@ -211,6 +217,7 @@ pub fn execute(
             belief_price,
             max_spread,
             to,
+            refund_excess,
             ..
         } => {
             offer_asset.info.check(deps.api)?;
@@ -229,6 +236,7 @@ pub fn execute(
                 belief_price,
                 max_spread,
                 to_addr,
+                refund_excess,
             )
         }
         ExecuteMsg::UpdateConfig { params } => update_config(deps, env, info, params),
@@ -282,6 +290,7 @@ pub fn receive_cw20(
                 belief_price,
                 max_spread,
                 to_addr,
+                false,
             )
         }
         Ok(Cw20HookMsg::WithdrawLiquidity { .. }) => withdraw_liquidity(
@@ -611,6 +620,8 @@ pub fn get_share_in_assets(pools: &[Asset], amount: Uint128, total_share: Uint12
 ///
 /// * **to** sets the recipient of the swap operation.
 ///
+/// * **refund_excess** refunds native tokens sent above the offer amount instead of failing.
+///
 /// NOTE - the address that wants to swap should approve the pair contract to pull the offer token.
 #[allow(clippy::too_many_arguments)]
 pub fn swap(
@@ -622,8 +633,14 @@ pub fn swap(
     belief_price: Option<Decimal>,
     max_spread: Option<Decimal>,
     to: Option<Addr>,
+    refund_excess: bool,
 ) -> Result<Response, ContractError> {
-    offer_asset.assert_sent_native_token_balance(&info)?;
+    let excess_amount = if refund_excess {
+        offer_asset.sent_native_token_excess(&info)?
+    } else {
+        offer_asset.assert_sent_native_token_balance(&info)?;
+        Uint128::zero()
+    };
 
     let mut config = CONFIG.load(deps.storage)?;
 
@@ -634,7 +651,7 @@ pub fn swap(
         .into_iter()
         .map(|mut p| {
             if p.info.equal(&offer_asset.info) {
-                p.amount = p.amount.checked_sub(offer_asset.amount)?;
+                p.amount = p.amount.checked_sub(offer_asset.amount + excess_amount)?;
             }
             Ok(p)
         })
@@ -668,6 +685,11 @@ pub fn swap(
         offer_amount,
         fee_info.total_fee_rate,
     )?;
+    let (return_amount, commission_amount) = apply_min_commission(
+        return_amount,
+        commission_amount,
+        min_commission_of(&config, &ask_pool.info),
+    )?;
 
     // Check the max spread limit (if it was specified)
     assert_max_spread(
@@ -702,6 +724,22 @@ pub fn swap(
         }
     }
 
+    // Send back the native tokens sent above the offer amount
+    if !excess_amount.is_zero() {
+        let refund_asset = Asset {
+            info: offer_asset.info.clone(),
+            amount: excess_amount,
+        };
+        messages.push(refund_asset.into_msg(&deps.querier, info.sender.clone())?)
+    }
+
+    // Accrue the liquidity providers' share of the commission
+    CUMULATIVE_FEES.update(deps.storage, &ask_pool.info, |fees| -> StdResult<_> {
+        Ok(fees
+            .unwrap_or_default()
+            .saturating_add(commission_amount - maker_fee_amount))
+    })?;
+
     if config.track_asset_balances {
         BALANCES.save(
             deps.storage,
@@ -745,7 +783,18 @@ pub fn swap(
             attr("spread_amount", spread_amount),
             attr("commission_amount", commission_amount),
             attr("maker_fee_amount", maker_fee_amount),
-        ]))
+        ])
+        .add_attributes(slippage_attributes(
+            belief_price,
+            offer_amount,
+            return_amount,
+        ))
+        .set_data(to_binary(&SwapResponseData {
+            return_amount,
+            spread_amount,
+            commission_amount,
+            execution_price: execution_price(offer_amount, return_amount),
+        })?))
 }
 
 /// Updates the pool configuration with the specified parameters in the `params` variable.
@@ -788,11 +837,54 @@ pub fn update_config(
                 "enabled".to_owned(),
             ));
         }
+        XYKPoolUpdateParams::UpdateMinCommission { min_commission } => {
+            check_min_commission(&config.pair_info.asset_infos, &min_commission)?;
+            config.min_commission = min_commission;
+
+            CONFIG.save(deps.storage, &config)?;
+
+            response
+                .attributes
+                .push(attr("min_commission".to_owned(), "updated".to_owned()));
+        }
     }
 
     Ok(response)
 }
 
+/// Checks that every minimum commission is set for a pool asset and that no asset is listed twice.
+///
+/// * **asset_infos** are the assets in the pool.
+///
+/// * **min_commission** is the minimum commission to check.
+fn check_min_commission(
+    asset_infos: &[AssetInfo],
+    min_commission: &[Asset],
+) -> Result<(), ContractError> {
+    for (i, asset) in min_commission.iter().enumerate() {
+        if !asset_infos.contains(&asset.info) {
+            return Err(ContractError::AssetMismatch {});
+        }
+        if min_commission[..i]
+            .iter()
+            .any(|other| other.info == asset.info)
+        {
+            return Err(ContractError::DoublingAssets {});
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the minimum commission charged for swaps to the specified ask asset (if one is set).
+fn min_commission_of(config: &Config, ask_asset_info: &AssetInfo) -> Option<Uint128> {
+    config
+        .min_commission
+        .iter()
+        .find(|asset| asset.info.equal(ask_asset_info))
+        .map(|asset| asset.amount)
+}
+
 /// Accumulate token prices for the assets in the pool.
 /// Note that this function shifts **block_time** when any of the token prices is zero in order to not
 /// fill an accumulator with a null price for that period.
@@ -881,6 +973,18 @@ pub fn calculate_maker_fee(
 ///
 /// * **QueryMsg::AssetBalanceAt { asset_info, block_height }** Returns the balance of the specified asset that was in the pool
 /// just preceeding the moment of the specified block height creation.
+///
+/// * **QueryMsg::Invariant {}** Returns the current pool invariant `k = x * y` computed from the raw reserves
+/// as a [`Decimal256`].
+///
+/// * **QueryMsg::ImpermanentLoss { entry_price }** Returns the impermanent loss of a position
+/// entered at the specified price as a [`Decimal256`] fraction.
+///
+/// * **QueryMsg::CumulativeFees {}** Returns the total amount of swap fees accrued to liquidity providers
+/// for each asset in the pool.
+///
+/// * **QueryMsg::TrackedHeightRange {}** Returns the range of block heights for which asset balances
+/// can be queried using a [`TrackedHeightRangeResponse`] object.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -899,6 +1003,12 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             asset_info,
             block_height,
         } => to_binary(&query_asset_balances_at(deps, asset_info, block_height)?),
+        QueryMsg::Invariant {} => to_binary(&query_invariant(deps)?),
+        QueryMsg::ImpermanentLoss { entry_price } => {
+            to_binary(&query_impermanent_loss(deps, entry_price)?)
+        }
+        QueryMsg::CumulativeFees {} => to_binary(&query_cumulative_fees(deps)?),
+        QueryMsg::TrackedHeightRange {} => to_binary(&query_tracked_height_range(deps, env)?),
         _ => Err(StdError::generic_err("Query is not supported")),
     }
 }
@@ -917,6 +1027,49 @@ pub fn query_pool(deps: Deps) -> StdResult<PoolResponse> {
     Ok(resp)
 }
 
+/// Returns the total amount of swap fees accrued to liquidity providers for each asset in the pool.
+pub fn query_cumulative_fees(deps: Deps) -> StdResult<Vec<Asset>> {
+    let config = CONFIG.load(deps.storage)?;
+
+    config
+        .pair_info
+        .asset_infos
+        .into_iter()
+        .map(|info| {
+            let amount = CUMULATIVE_FEES
+                .may_load(deps.storage, &info)?
+                .unwrap_or_default();
+            Ok(Asset { info, amount })
+        })
+        .collect()
+}
+
+/// Returns the current pool invariant `k = x * y` computed from the raw pool reserves.
+pub fn query_invariant(deps: Deps) -> StdResult<Decimal256> {
+    let config = CONFIG.load(deps.storage)?;
+    let (assets, _) = pool_info(deps.querier, &config)?;
+
+    let k = Uint256::from(assets[0].amount).checked_mul(assets[1].amount.into())?;
+
+    Decimal256::with_precision(k, 0u32)
+}
+
+/// Returns the impermanent loss of a position entered at `entry_price` against the current pool price.
+///
+/// * **entry_price** is the amount of the second pool asset paid per unit of the first one.
+pub fn query_impermanent_loss(deps: Deps, entry_price: Decimal) -> StdResult<Decimal256> {
+    let config = CONFIG.load(deps.storage)?;
+    let (assets, _) = pool_info(deps.querier, &config)?;
+
+    if assets[0].amount.is_zero() || assets[1].amount.is_zero() {
+        return Err(StdError::generic_err("Pools are empty"));
+    }
+
+    let current_price = Decimal256::from_ratio(assets[1].amount, assets[0].amount);
+
+    xyk_impermanent_loss(entry_price, current_price)
+}
+
 /// Returns the amount of assets that could be withdrawn from the pool using a specific amount of LP tokens.
 /// The result is returned in a vector that contains objects of type [`Asset`].
 ///
@@ -956,8 +1109,8 @@ pub fn query_simulation(deps: Deps, offer_asset: Asset) -> StdResult<SimulationR
     // Get fee info from the factory contract
     let fee_info = query_fee_info(
         &deps.querier,
-        config.factory_addr,
-        config.pair_info.pair_type,
+        &config.factory_addr,
+        config.pair_info.pair_type.clone(),
     )?;
 
     let (return_amount, spread_amount, commission_amount) = compute_swap(
@@ -966,6 +1119,12 @@ pub fn query_simulation(deps: Deps, offer_asset: Asset) -> StdResult<SimulationR
         offer_asset.amount,
         fee_info.total_fee_rate,
     )?;
+    let (return_amount, commission_amount) = apply_min_commission(
+        return_amount,
+        commission_amount,
+        min_commission_of(&config, &ask_pool.info),
+    )
+    .map_err(|err| StdError::generic_err(err.to_string()))?;
 
     Ok(SimulationResponse {
         return_amount,
@@ -1005,17 +1164,30 @@ pub fn query_reverse_simulation(
     // Get fee info from factory
     let fee_info = query_fee_info(
         &deps.querier,
-        config.factory_addr,
-        config.pair_info.pair_type,
+        &config.factory_addr,
+        config.pair_info.pair_type.clone(),
     )?;
 
-    let (offer_amount, spread_amount, commission_amount) = compute_offer_amount(
+    let (mut offer_amount, mut spread_amount, mut commission_amount) = compute_offer_amount(
         offer_pool.amount,
         ask_pool.amount,
         ask_asset.amount,
         fee_info.total_fee_rate,
     )?;
 
+    // If the minimum commission applies, the swap has to return the ask amount plus the floor
+    if let Some(min_commission) = min_commission_of(&config, &ask_pool.info) {
+        if commission_amount < min_commission {
+            (offer_amount, spread_amount, _) = compute_offer_amount(
+                offer_pool.amount,
+                ask_pool.amount,
+                ask_asset.amount.checked_add(min_commission)?,
+                Decimal::zero(),
+            )?;
+            commission_amount = min_commission;
+        }
+    }
+
     Ok(ReverseSimulationResponse {
         offer_amount,
         spread_amount,
@@ -1067,6 +1239,7 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
         block_time_last: config.block_time_last,
         params: Some(to_binary(&XYKPoolConfig {
             track_asset_balances: config.track_asset_balances,
+            min_commission: config.min_commission,
         })?),
         owner: None,
     })
@@ -1083,6 +1256,30 @@ pub fn query_asset_balances_at(
     BALANCES.may_load_at_height(deps.storage, &asset_info, block_height.u64())
 }
 
+/// Returns the range of block heights for which [`query_asset_balances_at`] returns a balance.
+/// Balances are snapshotted starting from the block in which tracking was enabled,
+/// so the earliest queryable height is the block right after it.
+pub fn query_tracked_height_range(deps: Deps, env: Env) -> StdResult<TrackedHeightRangeResponse> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let earliest = if config.track_asset_balances {
+        BALANCES
+            .changelog()
+            .prefix(&config.pair_info.asset_infos[0])
+            .keys(deps.storage, None, None, Order::Ascending)
+            .next()
+            .transpose()?
+            .map(|height| height + 1)
+    } else {
+        None
+    };
+
+    Ok(TrackedHeightRangeResponse {
+        earliest,
+        latest: env.block.height,
+    })
+}
+
 /// Returns the result of a swap.
 ///
 /// * **offer_pool** total amount of offer assets in the pool.
@@ -1168,6 +1365,32 @@ pub fn compute_offer_amount(
     Ok((offer_amount, spread_amount, commission_amount.try_into()?))
 }
 
+/// Raises the swap commission to the pool's minimum commission (if one is set).
+/// Returns the new return and commission amounts.
+///
+/// * **return_amount** amount of ask assets returned after the commission was deducted.
+///
+/// * **commission_amount** commission computed using the pool's fee rate.
+///
+/// * **min_commission** minimum commission charged per swap.
+pub fn apply_min_commission(
+    return_amount: Uint128,
+    commission_amount: Uint128,
+    min_commission: Option<Uint128>,
+) -> Result<(Uint128, Uint128), ContractError> {
+    match min_commission {
+        Some(min_commission) if commission_amount < min_commission => {
+            let gross_return = return_amount + commission_amount;
+            if gross_return <= min_commission {
+                return Err(ContractError::MinCommissionNotCovered {});
+            }
+
+            Ok((gross_return - min_commission, min_commission))
+        }
+        _ => Ok((return_amount, commission_amount)),
+    }
+}
+
 /// If `belief_price` and `max_spread` are both specified, we compute a new spread,
 /// otherwise we just use the swap spread to check `max_spread`.
 ///
@@ -52,6 +52,12 @@ pub enum ContractError {
 
     #[error("Asset balances tracking is already enabled")]
     AssetBalancesTrackingIsAlreadyEnabled {},
+
+    #[error("LP token is already set")]
+    LpTokenAlreadySet {},
+
+    #[error("Swap output doesn't cover the pool's minimum commission")]
+    MinCommissionNotCovered {},
 }
 
 impl From<OverflowError> for ContractError {
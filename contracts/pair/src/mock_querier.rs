@@ -5,8 +5,8 @@ use cosmwasm_std::{
 };
 use std::collections::HashMap;
 
-use astroport::factory::FeeInfoResponse;
 use astroport::factory::QueryMsg::FeeInfo;
+use astroport::factory::{Config as FactoryConfig, FeeInfoResponse};
 use cw20::{BalanceResponse, Cw20QueryMsg, TokenInfoResponse};
 
 /// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies.
@@ -142,9 +142,25 @@ impl WasmMockQuerier {
                     }
                 }
             }
-            QueryRequest::Wasm(WasmQuery::Raw { contract_addr, .. }) => {
+            QueryRequest::Wasm(WasmQuery::Raw { contract_addr, key }) => {
                 if contract_addr == "factory" {
-                    SystemResult::Ok(to_binary(&Vec::<Addr>::new()).into())
+                    if key.as_slice() == b"config".as_slice() {
+                        SystemResult::Ok(
+                            to_binary(&FactoryConfig {
+                                owner: Addr::unchecked("owner"),
+                                token_code_id: 0,
+                                fee_address: Some(Addr::unchecked("fee_address")),
+                                generator_address: None,
+                                whitelist_code_id: 0,
+                                coin_registry_address: Addr::unchecked("coin_registry"),
+                            })
+                            .into(),
+                        )
+                    } else {
+                        SystemResult::Ok(to_binary(&Vec::<Addr>::new()).into())
+                    }
+                } else if contract_addr == "coin_registry" {
+                    SystemResult::Ok(to_binary(&6).into())
                 } else {
                     panic!("DO NOT ENTER HERE");
                 }
@@ -1,7 +1,7 @@
-use astroport::asset::{AssetInfo, PairInfo};
+use astroport::asset::{Asset, AssetInfo, PairInfo};
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{Addr, Uint128};
-use cw_storage_plus::{Item, SnapshotMap};
+use cw_storage_plus::{Item, Map, SnapshotMap};
 
 /// This structure stores the main config parameters for a constant product pair contract.
 #[cw_serde]
@@ -18,6 +18,9 @@ pub struct Config {
     pub price1_cumulative_last: Uint128,
     /// Whether asset balances are tracked over blocks or not.
     pub track_asset_balances: bool,
+    /// The minimum commission charged per swap for each ask asset
+    #[serde(default)]
+    pub min_commission: Vec<Asset>,
 }
 
 /// Stores the config struct at the given key
@@ -30,3 +33,6 @@ pub const BALANCES: SnapshotMap<&AssetInfo, Uint128> = SnapshotMap::new(
     "balances_change",
     cw_storage_plus::Strategy::EveryBlock,
 );
+
+/// Stores the total amount of swap fees accrued to liquidity providers for each asset
+pub const CUMULATIVE_FEES: Map<&AssetInfo, Uint128> = Map::new("cumulative_fees");
@@ -1,24 +1,27 @@
 use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    attr, to_binary, Addr, BankMsg, BlockInfo, Coin, CosmosMsg, Decimal, DepsMut, Env, Reply,
-    ReplyOn, Response, StdError, SubMsg, SubMsgResponse, SubMsgResult, Timestamp, Uint128, WasmMsg,
+    attr, from_binary, to_binary, Addr, BankMsg, BlockInfo, Coin, CosmosMsg, Decimal, Decimal256,
+    DepsMut, Env, Reply, ReplyOn, Response, StdError, SubMsg, SubMsgResponse, SubMsgResult,
+    Timestamp, Uint128, WasmMsg,
 };
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
 use proptest::prelude::*;
 use protobuf::Message;
+use std::str::FromStr;
 
-use astroport::asset::{Asset, AssetInfo, PairInfo};
+use astroport::asset::{format_lp_token_name, native_asset, Asset, AssetInfo, PairInfo};
 use astroport::factory::PairType;
 use astroport::pair::{
-    Cw20HookMsg, ExecuteMsg, InstantiateMsg, PoolResponse, ReverseSimulationResponse,
-    SimulationResponse, TWAP_PRECISION,
+    ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, PoolResponse, QueryMsg,
+    ReverseSimulationResponse, SimulationResponse, SwapResponseData, XYKPoolConfig, XYKPoolParams,
+    XYKPoolUpdateParams, MAX_ALLOWED_SLIPPAGE, TWAP_PRECISION,
 };
 use astroport::token::InstantiateMsg as TokenInstantiateMsg;
 
 use crate::contract::compute_offer_amount;
 use crate::contract::reply;
 use crate::contract::{
-    accumulate_prices, assert_max_spread, compute_swap, execute, instantiate, query_pool,
+    accumulate_prices, assert_max_spread, compute_swap, execute, instantiate, query, query_pool,
     query_reverse_simulation, query_share, query_simulation,
 };
 use crate::error::ContractError;
@@ -26,7 +29,7 @@ use crate::mock_querier::mock_dependencies;
 use crate::response::MsgInstantiateContractResponse;
 use crate::state::{Config, CONFIG};
 
-fn store_liquidity_token(deps: DepsMut, msg_id: u64, contract_addr: String) {
+fn lp_token_reply(msg_id: u64, contract_addr: String) -> Reply {
     let data = MsgInstantiateContractResponse {
         contract_address: contract_addr,
         data: vec![],
@@ -36,15 +39,17 @@ fn store_liquidity_token(deps: DepsMut, msg_id: u64, contract_addr: String) {
     .write_to_bytes()
     .unwrap();
 
-    let reply_msg = Reply {
+    Reply {
         id: msg_id,
         result: SubMsgResult::Ok(SubMsgResponse {
             events: vec![],
             data: Some(data.into()),
         }),
-    };
+    }
+}
 
-    let _res = reply(deps, mock_env(), reply_msg.clone()).unwrap();
+fn store_liquidity_token(deps: DepsMut, msg_id: u64, contract_addr: String) {
+    let _res = reply(deps, mock_env(), lp_token_reply(msg_id, contract_addr)).unwrap();
 }
 
 #[test]
@@ -122,6 +127,85 @@ fn proper_initialization() {
     );
 }
 
+#[test]
+fn duplicate_lp_token_reply_is_rejected() {
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(123u128))],
+    )]);
+
+    let msg = InstantiateMsg {
+        factory_addr: String::from("factory"),
+        asset_infos: vec![
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        init_params: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // Store liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+
+    let err = reply(
+        deps.as_mut(),
+        env,
+        lp_token_reply(1, "liquidity0001".to_string()),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::LpTokenAlreadySet {});
+
+    let pair_info = CONFIG.load(deps.as_ref().storage).unwrap().pair_info;
+    assert_eq!(Addr::unchecked("liquidity0000"), pair_info.liquidity_token);
+}
+
+#[test]
+fn lp_token_name_preview_matches_instantiate() {
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(123u128))],
+    )]);
+
+    let asset_infos = vec![
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        },
+    ];
+    let preview = format_lp_token_name(&asset_infos, &deps.as_ref().querier).unwrap();
+
+    let msg = InstantiateMsg {
+        factory_addr: String::from("factory"),
+        asset_infos,
+        token_code_id: 10u64,
+        init_params: None,
+    };
+    let res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Instantiate { msg, .. }) => {
+            let token_msg: TokenInstantiateMsg = from_binary(msg).unwrap();
+            assert_eq!(token_msg.name, preview);
+            assert_eq!(token_msg.name, "UUSD-MAPP-LP");
+        }
+        _ => panic!("Expected the LP token instantiate message"),
+    }
+}
+
 #[test]
 fn provide_liquidity() {
     let mut deps = mock_dependencies(&[Coin {
@@ -798,6 +882,7 @@ fn try_native_to_token() {
         belief_price: None,
         max_spread: Some(Decimal::percent(50)),
         to: None,
+        refund_excess: false,
     };
     let env = mock_env_with_block_time(1000);
     let info = mock_info(
@@ -927,6 +1012,19 @@ fn try_native_to_token() {
         ]
     );
 
+    // Callers executing the swap through a sub-message can read the result from the data
+    let data: SwapResponseData = from_binary(&res.data.clone().unwrap()).unwrap();
+    assert_eq!(
+        data,
+        SwapResponseData {
+            return_amount: expected_return_amount,
+            spread_amount: expected_spread_amount,
+            commission_amount: expected_commission_amount,
+            // 1.579739216860712529 = 1500000000 / 949523810
+            execution_price: Decimal256::from_str("1.579739216860712529").unwrap(),
+        }
+    );
+
     assert_eq!(
         &SubMsg {
             msg: WasmMsg::Execute {
@@ -948,16 +1046,17 @@ fn try_native_to_token() {
 }
 
 #[test]
-fn try_token_to_native() {
-    let total_share = Uint128::new(20000000000u128);
-    let asset_pool_amount = Uint128::new(30000000000u128);
-    let collateral_pool_amount = Uint128::new(20000000000u128);
-    let offer_amount = Uint128::new(1500000000u128);
+fn swap_with_execution_price_above_decimal_max() {
+    let total_share = Uint128::new(1000u128);
+    let asset_pool_amount = Uint128::new(10u128);
+    let collateral_pool_amount = Uint128::new(10_000_000_000_000_000_000u128);
+    let offer_amount = Uint128::new(10_000_000_000_000_000_000_000u128);
 
     let mut deps = mock_dependencies(&[Coin {
         denom: "uusd".to_string(),
-        amount: collateral_pool_amount,
+        amount: collateral_pool_amount + offer_amount, /* user deposit must be pre-applied */
     }]);
+
     deps.querier.with_token_balances(&[
         (
             &String::from("liquidity0000"),
@@ -965,10 +1064,7 @@ fn try_token_to_native() {
         ),
         (
             &String::from("asset0000"),
-            &[(
-                &String::from(MOCK_CONTRACT_ADDR),
-                &(asset_pool_amount + offer_amount),
-            )],
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
         ),
     ]);
 
@@ -988,63 +1084,61 @@ fn try_token_to_native() {
 
     let env = mock_env();
     let info = mock_info("addr0000", &[]);
-    // We can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
-
-    // Store liquidity token
+    instantiate(deps.as_mut(), env, info, msg).unwrap();
     store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
 
-    // Unauthorized access; can not execute swap directly for token swap
+    // The belief price is the highest one a Decimal can hold, the swap pays even more
     let msg = ExecuteMsg::Swap {
         offer_asset: Asset {
-            info: AssetInfo::Token {
-                contract_addr: Addr::unchecked("asset0000"),
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
             },
             amount: offer_amount,
         },
         ask_asset_info: None,
-        belief_price: None,
-        max_spread: None,
+        belief_price: Some(Decimal::MAX),
+        max_spread: Some(Decimal::percent(50)),
         to: None,
+        refund_excess: false,
     };
     let env = mock_env_with_block_time(1000);
-    let info = mock_info("addr0000", &[]);
-    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
-    assert_eq!(res, ContractError::Cw20DirectSwap {});
-
-    // Normal sell
-    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
-        sender: String::from("addr0000"),
-        amount: offer_amount,
-        msg: to_binary(&Cw20HookMsg::Swap {
-            ask_asset_info: None,
-            belief_price: None,
-            max_spread: Some(Decimal::percent(50)),
-            to: None,
-        })
-        .unwrap(),
-    });
-    let env = mock_env_with_block_time(1000);
-    let info = mock_info("asset0000", &[]);
-
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
-    let msg_transfer = res.messages.get(0).expect("no message");
 
-    // Current price is 1.5, so expected return without spread is 1000
-    // 952380952,3809524 = 20000000000 - (30000000000 * 20000000000) / (30000000000 + 1500000000)
-    let expected_ret_amount = Uint128::new(952_380_952u128);
+    // 9 = 10 - (10^19 * 10) / (10^19 + 10^22), the commission rounds down to zero
+    let expected_return_amount = Uint128::new(9u128);
+    // 1111111111111111111111.111111111111111111 = 10^22 / 9, which is above Decimal::MAX
+    let expected_execution_price =
+        Decimal256::from_str("1111111111111111111111.111111111111111111").unwrap();
+
+    let data: SwapResponseData = from_binary(&res.data.unwrap()).unwrap();
+    assert_eq!(data.return_amount, expected_return_amount);
+    assert_eq!(data.execution_price, expected_execution_price);
+    assert!(res.attributes.contains(&attr(
+        "execution_price",
+        expected_execution_price.to_string()
+    )));
+}
 
-    // 47619047 = 1500000000 * (20000000000 / 30000000000) - 952380952,3809524
-    let expected_spread_amount = Uint128::new(47619047u128);
+#[test]
+fn swap_refunds_over_sent_native_tokens() {
+    let total_share = Uint128::new(30000000000u128);
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+    let offer_amount = Uint128::new(1500000000u128);
+    let excess_amount = Uint128::new(500000u128);
 
-    let expected_commission_amount = expected_ret_amount.multiply_ratio(3u128, 1000u128); // 0.3%
-    let expected_maker_fee_amount = expected_commission_amount.multiply_ratio(166u128, 1000u128);
-    let expected_return_amount = expected_ret_amount
-        .checked_sub(expected_commission_amount)
-        .unwrap();
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount + offer_amount + excess_amount, /* user deposit must be pre-applied */
+    }]);
 
-    // Check simulation res
-    // Return asset token balance as normal
     deps.querier.with_token_balances(&[
         (
             &String::from("liquidity0000"),
@@ -1052,95 +1146,528 @@ fn try_token_to_native() {
         ),
         (
             &String::from("asset0000"),
-            &[(&String::from(MOCK_CONTRACT_ADDR), &(asset_pool_amount))],
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
         ),
     ]);
 
-    let simulation_res: SimulationResponse = query_simulation(
-        deps.as_ref(),
-        Asset {
-            amount: offer_amount,
-            info: AssetInfo::Token {
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
                 contract_addr: Addr::unchecked("asset0000"),
             },
-        },
-    )
-    .unwrap();
-    assert_eq!(expected_return_amount, simulation_res.return_amount);
-    assert_eq!(expected_commission_amount, simulation_res.commission_amount);
-    assert_eq!(expected_spread_amount, simulation_res.spread_amount);
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+    };
 
-    // Check reverse simulation result
-    let reverse_simulation_res: ReverseSimulationResponse = query_reverse_simulation(
-        deps.as_ref(),
-        Asset {
-            amount: expected_return_amount,
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env, info, msg).unwrap();
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+
+    let swap_msg = |refund_excess| ExecuteMsg::Swap {
+        offer_asset: Asset {
             info: AssetInfo::NativeToken {
                 denom: "uusd".to_string(),
             },
+            amount: offer_amount,
         },
-    )
-    .unwrap();
-    assert_eq!(
-        (offer_amount.u128() as i128 - reverse_simulation_res.offer_amount.u128() as i128).abs()
-            < 5i128,
-        true
-    );
-    assert_eq!(
-        (expected_commission_amount.u128() as i128
-            - reverse_simulation_res.commission_amount.u128() as i128)
-            .abs()
-            < 5i128,
-        true
-    );
-    assert_eq!(
-        (expected_spread_amount.u128() as i128
-            - reverse_simulation_res.spread_amount.u128() as i128)
-            .abs()
-            < 5i128,
-        true
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        refund_excess,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount + excess_amount,
+        }],
     );
 
+    // Without the flag an over-send is still rejected
+    let err = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(1000),
+        info.clone(),
+        swap_msg(false),
+    )
+    .unwrap_err();
     assert_eq!(
-        res.attributes,
-        vec![
-            attr("action", "swap"),
-            attr("sender", "addr0000"),
-            attr("receiver", "addr0000"),
-            attr("offer_asset", "asset0000"),
-            attr("ask_asset", "uusd"),
-            attr("offer_amount", offer_amount.to_string()),
-            attr("return_amount", expected_return_amount.to_string()),
-            attr("tax_amount", Uint128::zero().to_string()),
-            attr("spread_amount", expected_spread_amount.to_string()),
-            attr("commission_amount", expected_commission_amount.to_string()),
-            attr("maker_fee_amount", expected_maker_fee_amount.to_string()),
-        ]
+        err,
+        ContractError::Std(StdError::generic_err(
+            "Native token balance mismatch between the argument and the transferred",
+        ))
     );
 
+    let res = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(1000),
+        info,
+        swap_msg(true),
+    )
+    .unwrap();
+
+    // The excess is not part of the offer pool, so the swap matches one with the exact amount
+    let expected_ret_amount = Uint128::new(952_380_952u128);
+    let expected_commission_amount = expected_ret_amount.multiply_ratio(3u128, 1000u128);
+    let expected_return_amount = expected_ret_amount
+        .checked_sub(expected_commission_amount)
+        .unwrap();
+
+    // The return transfer, the Maker fee and the refund
+    assert_eq!(res.messages.len(), 3);
     assert_eq!(
-        &SubMsg {
-            msg: CosmosMsg::Bank(BankMsg::Send {
-                to_address: String::from("addr0000"),
-                amount: vec![Coin {
-                    denom: "uusd".to_string(),
-                    amount: expected_return_amount
-                }],
+        res.messages[0],
+        SubMsg::new(WasmMsg::Execute {
+            contract_addr: String::from("asset0000"),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: String::from("addr0000"),
+                amount: expected_return_amount,
             })
-            .into(),
-            id: 0,
-            gas_limit: None,
-            reply_on: ReplyOn::Never,
-        },
-        msg_transfer,
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+    assert_eq!(
+        res.messages[2],
+        SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("addr0000"),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: excess_amount,
+            }],
+        }))
     );
+}
 
-    // Failed due to trying to swap a non token (specifying an address of a non token contract)
-    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
-        sender: String::from("addr0000"),
-        amount: offer_amount,
-        msg: to_binary(&Cw20HookMsg::Swap {
-            ask_asset_info: None,
+#[test]
+fn swap_respects_min_commission() {
+    let total_share = Uint128::new(30000000000u128);
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount,
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+
+    let token = |amount: u128| Asset {
+        info: AssetInfo::Token {
+            contract_addr: Addr::unchecked("asset0000"),
+        },
+        amount: Uint128::new(amount),
+    };
+    let uusd = |amount: u128| Asset {
+        info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        amount: Uint128::new(amount),
+    };
+
+    let instantiate_msg = |min_commission: Vec<Asset>| InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: Some(
+            to_binary(&XYKPoolParams {
+                track_asset_balances: None,
+                min_commission: Some(min_commission),
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+
+    // The minimum commission can only be set for the pool assets, once per asset
+    let err = instantiate(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        instantiate_msg(vec![native_asset(
+            "uluna".to_string(),
+            Uint128::new(100u128),
+        )]),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::AssetMismatch {});
+    let err = instantiate(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        instantiate_msg(vec![token(100), token(200)]),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::DoublingAssets {});
+
+    // Only swaps to the token are charged the minimum commission
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        instantiate_msg(vec![token(100)]),
+    )
+    .unwrap();
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+
+    let res: ConfigResponse =
+        from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap()).unwrap();
+    let params: XYKPoolConfig = from_binary(&res.params.unwrap()).unwrap();
+    assert_eq!(params.min_commission, vec![token(100)]);
+
+    // 666 = 20000000000 - (30000000000 * 20000000000) / (30000000000 + 1000)
+    // The 0.3% commission rounds down to 1, so the 100 floor is charged instead
+    let simulation_res = query_simulation(deps.as_ref(), uusd(1000)).unwrap();
+    assert_eq!(simulation_res.return_amount, Uint128::new(566u128));
+    assert_eq!(simulation_res.commission_amount, Uint128::new(100u128));
+
+    // Returning 566 needs 666 before the floor is deducted
+    let reverse_simulation_res = query_reverse_simulation(
+        deps.as_ref(),
+        Asset {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+            amount: Uint128::new(566u128),
+        },
+    )
+    .unwrap();
+    assert_eq!(reverse_simulation_res.offer_amount, Uint128::new(999u128));
+    assert_eq!(
+        reverse_simulation_res.commission_amount,
+        Uint128::new(100u128)
+    );
+
+    // 66 = 20000000000 - (30000000000 * 20000000000) / (30000000000 + 100) can't pay the floor
+    let err = query_simulation(deps.as_ref(), uusd(100)).unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("Swap output doesn't cover the pool's minimum commission")
+    );
+
+    // 1499 = 30000000000 - (20000000000 * 30000000000) / (20000000000 + 1000)
+    // No floor is set for uusd, so only the regular 0.3% commission is charged
+    let simulation_res = query_simulation(deps.as_ref(), token(1000)).unwrap();
+    assert_eq!(simulation_res.return_amount, Uint128::new(1495u128));
+    assert_eq!(simulation_res.commission_amount, Uint128::new(4u128));
+
+    let swap_msg = |amount: u128| ExecuteMsg::Swap {
+        offer_asset: uusd(amount),
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: Some(Decimal::percent(50)),
+        to: None,
+        refund_excess: false,
+    };
+    let funds = |amount: u128| {
+        mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(amount),
+            }],
+        )
+    };
+
+    /* user deposit must be pre-applied */
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount + Uint128::new(1000u128),
+        }],
+    )]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(1000),
+        funds(1000),
+        swap_msg(1000),
+    )
+    .unwrap();
+    assert!(res.attributes.contains(&attr("return_amount", "566")));
+    assert!(res.attributes.contains(&attr("commission_amount", "100")));
+    // 16 = 100 * 0.166
+    assert!(res.attributes.contains(&attr("maker_fee_amount", "16")));
+
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount + Uint128::new(100u128),
+        }],
+    )]);
+    let err = execute(
+        deps.as_mut(),
+        mock_env_with_block_time(1000),
+        funds(100),
+        swap_msg(100),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::MinCommissionNotCovered {});
+
+    // Only the factory owner can update the minimum commission
+    let update_msg = |min_commission: Vec<Asset>| ExecuteMsg::UpdateConfig {
+        params: to_binary(&XYKPoolUpdateParams::UpdateMinCommission { min_commission }).unwrap(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        update_msg(vec![]),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        update_msg(vec![uusd(10), uusd(10)]),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::DoublingAssets {});
+
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        update_msg(vec![uusd(10)]),
+    )
+    .unwrap();
+
+    let res: ConfigResponse =
+        from_binary(&query(deps.as_ref(), env, QueryMsg::Config {}).unwrap()).unwrap();
+    let params: XYKPoolConfig = from_binary(&res.params.unwrap()).unwrap();
+    assert_eq!(params.min_commission, vec![uusd(10)]);
+
+    // The token floor is gone, swaps to uusd are now charged at least 10
+    let simulation_res = query_simulation(deps.as_ref(), uusd(100)).unwrap();
+    assert_eq!(simulation_res.commission_amount, Uint128::zero());
+    let simulation_res = query_simulation(deps.as_ref(), token(1000)).unwrap();
+    assert_eq!(simulation_res.return_amount, Uint128::new(1489u128));
+    assert_eq!(simulation_res.commission_amount, Uint128::new(10u128));
+}
+
+#[test]
+fn try_token_to_native() {
+    let total_share = Uint128::new(20000000000u128);
+    let asset_pool_amount = Uint128::new(30000000000u128);
+    let collateral_pool_amount = Uint128::new(20000000000u128);
+    let offer_amount = Uint128::new(1500000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount,
+    }]);
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &(asset_pool_amount + offer_amount),
+            )],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    // We can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+
+    // Unauthorized access; can not execute swap directly for token swap
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+            amount: offer_amount,
+        },
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        refund_excess: false,
+    };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    assert_eq!(res, ContractError::Cw20DirectSwap {});
+
+    // Normal sell
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: offer_amount,
+        msg: to_binary(&Cw20HookMsg::Swap {
+            ask_asset_info: None,
+            belief_price: None,
+            max_spread: Some(Decimal::percent(50)),
+            to: None,
+        })
+        .unwrap(),
+    });
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info("asset0000", &[]);
+
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    let msg_transfer = res.messages.get(0).expect("no message");
+
+    // Current price is 1.5, so expected return without spread is 1000
+    // 952380952,3809524 = 20000000000 - (30000000000 * 20000000000) / (30000000000 + 1500000000)
+    let expected_ret_amount = Uint128::new(952_380_952u128);
+
+    // 47619047 = 1500000000 * (20000000000 / 30000000000) - 952380952,3809524
+    let expected_spread_amount = Uint128::new(47619047u128);
+
+    let expected_commission_amount = expected_ret_amount.multiply_ratio(3u128, 1000u128); // 0.3%
+    let expected_maker_fee_amount = expected_commission_amount.multiply_ratio(166u128, 1000u128);
+    let expected_return_amount = expected_ret_amount
+        .checked_sub(expected_commission_amount)
+        .unwrap();
+
+    // Check simulation res
+    // Return asset token balance as normal
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &(asset_pool_amount))],
+        ),
+    ]);
+
+    let simulation_res: SimulationResponse = query_simulation(
+        deps.as_ref(),
+        Asset {
+            amount: offer_amount,
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        },
+    )
+    .unwrap();
+    assert_eq!(expected_return_amount, simulation_res.return_amount);
+    assert_eq!(expected_commission_amount, simulation_res.commission_amount);
+    assert_eq!(expected_spread_amount, simulation_res.spread_amount);
+
+    // Check reverse simulation result
+    let reverse_simulation_res: ReverseSimulationResponse = query_reverse_simulation(
+        deps.as_ref(),
+        Asset {
+            amount: expected_return_amount,
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        (offer_amount.u128() as i128 - reverse_simulation_res.offer_amount.u128() as i128).abs()
+            < 5i128,
+        true
+    );
+    assert_eq!(
+        (expected_commission_amount.u128() as i128
+            - reverse_simulation_res.commission_amount.u128() as i128)
+            .abs()
+            < 5i128,
+        true
+    );
+    assert_eq!(
+        (expected_spread_amount.u128() as i128
+            - reverse_simulation_res.spread_amount.u128() as i128)
+            .abs()
+            < 5i128,
+        true
+    );
+
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "swap"),
+            attr("sender", "addr0000"),
+            attr("receiver", "addr0000"),
+            attr("offer_asset", "asset0000"),
+            attr("ask_asset", "uusd"),
+            attr("offer_amount", offer_amount.to_string()),
+            attr("return_amount", expected_return_amount.to_string()),
+            attr("tax_amount", Uint128::zero().to_string()),
+            attr("spread_amount", expected_spread_amount.to_string()),
+            attr("commission_amount", expected_commission_amount.to_string()),
+            attr("maker_fee_amount", expected_maker_fee_amount.to_string()),
+        ]
+    );
+
+    assert_eq!(
+        &SubMsg {
+            msg: CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("addr0000"),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: expected_return_amount
+                }],
+            })
+            .into(),
+            id: 0,
+            gas_limit: None,
+            reply_on: ReplyOn::Never,
+        },
+        msg_transfer,
+    );
+
+    // Failed due to trying to swap a non token (specifying an address of a non token contract)
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: offer_amount,
+        msg: to_binary(&Cw20HookMsg::Swap {
+            ask_asset_info: None,
             belief_price: None,
             max_spread: None,
             to: None,
@@ -1153,6 +1680,282 @@ fn try_token_to_native() {
     assert_eq!(res, ContractError::Unauthorized {});
 }
 
+#[test]
+fn swap_reports_slippage_attributes() {
+    let total_share = Uint128::new(30000000000u128);
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+    let offer_amount = Uint128::new(1500000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount + offer_amount, /* user deposit must be pre-applied */
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+
+    // The trader expects 1.5 uusd per asset0000 and tolerates up to 10% of slippage
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        ask_asset_info: None,
+        belief_price: Some(Decimal::from_ratio(3u128, 2u128)),
+        max_spread: Some(Decimal::percent(10)),
+        to: None,
+        refund_excess: false,
+    };
+    let env = mock_env_with_block_time(1000);
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+
+    // 949523810 = 952380952 - 0.3% commission
+    assert_eq!(
+        res.attributes
+            .iter()
+            .find(|attribute| attribute.key == "return_amount")
+            .unwrap()
+            .value,
+        "949523810"
+    );
+    // 1.579739216860712529 = 1500000000 / 949523810
+    // 0.053159477907141686 = (1.579739216860712529 - 1.5) / 1.5
+    assert_eq!(
+        res.attributes[res.attributes.len() - 3..],
+        [
+            attr("belief_price", "1.5"),
+            attr("execution_price", "1.579739216860712529"),
+            attr("price_deviation", "0.053159477907141686"),
+        ]
+    );
+}
+
+#[test]
+fn swap_accrues_cumulative_fees() {
+    let total_share = Uint128::new(30000000000u128);
+    let asset_pool_amount = Uint128::new(20000000000u128);
+    let collateral_pool_amount = Uint128::new(30000000000u128);
+    let offer_amount = Uint128::new(1500000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount + offer_amount, /* user deposit must be pre-applied */
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_pool_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // Store liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+
+    let res: Vec<Asset> =
+        from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::CumulativeFees {}).unwrap())
+            .unwrap();
+    assert!(res.iter().all(|asset| asset.amount.is_zero()));
+
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: Some(Decimal::percent(50)),
+        to: None,
+        refund_excess: false,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+
+    // The mocked pool balances don't change, so both swaps charge the same commission
+    execute(
+        deps.as_mut(),
+        mock_env_with_block_time(1000),
+        info.clone(),
+        msg.clone(),
+    )
+    .unwrap();
+    execute(deps.as_mut(), mock_env_with_block_time(2000), info, msg).unwrap();
+
+    // 2857142 = 952380952 * 0.3% commission
+    // 474285 = 2857142 * 0.166 maker fee
+    // 4765714 = 2 * (2857142 - 474285)
+    let res: Vec<Asset> =
+        from_binary(&query(deps.as_ref(), env, QueryMsg::CumulativeFees {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        vec![
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::zero(),
+            },
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                amount: Uint128::new(4765714u128),
+            },
+        ]
+    );
+}
+
+#[test]
+fn swap_rejects_extra_native_funds() {
+    let offer_amount = Uint128::new(1500000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(30000000000u128) + offer_amount,
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(30000000000u128),
+            )],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(20000000000u128),
+            )],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    // Store liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: Some(Decimal::percent(50)),
+        to: None,
+        refund_excess: false,
+    };
+
+    // An unrelated coin attached next to the offer would otherwise be stranded in the pool
+    let info = mock_info(
+        "addr0000",
+        &[
+            Coin {
+                denom: "uusd".to_string(),
+                amount: offer_amount,
+            },
+            Coin {
+                denom: "uluna".to_string(),
+                amount: Uint128::new(1000u128),
+            },
+        ],
+    );
+    let err = execute(deps.as_mut(), mock_env_with_block_time(1000), info, msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Std(StdError::generic_err("Sent more than one denomination"))
+    );
+}
+
 #[test]
 fn test_max_spread() {
     assert_max_spread(
@@ -1201,6 +2004,50 @@ fn test_max_spread() {
     .unwrap_err();
 }
 
+#[test]
+fn test_max_spread_bounds() {
+    // Zero tolerance only accepts swaps without any spread
+    assert_max_spread(
+        None,
+        Some(Decimal::zero()),
+        Uint128::zero(),
+        Uint128::from(1000000u128),
+        Uint128::zero(),
+    )
+    .unwrap();
+
+    let err = assert_max_spread(
+        None,
+        Some(Decimal::zero()),
+        Uint128::zero(),
+        Uint128::from(999999u128),
+        Uint128::from(1u128),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::MaxSpreadAssertion {});
+
+    // The maximum allowed tolerance is inclusive
+    assert_max_spread(
+        None,
+        Some(Decimal::from_str(MAX_ALLOWED_SLIPPAGE).unwrap()),
+        Uint128::zero(),
+        Uint128::from(500000u128),
+        Uint128::from(500000u128),
+    )
+    .unwrap();
+
+    // Anything above the maximum is rejected even if the spread itself would fit
+    let err = assert_max_spread(
+        None,
+        Some(Decimal::percent(200)),
+        Uint128::zero(),
+        Uint128::from(1000000u128),
+        Uint128::zero(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::AllowedSpreadAssertion {});
+}
+
 #[test]
 #[ignore]
 fn test_deduct() {
@@ -1296,6 +2143,117 @@ fn test_query_pool() {
     assert_eq!(res.total_share, total_share_amount);
 }
 
+#[test]
+fn test_query_invariant() {
+    let total_share_amount = Uint128::from(111u128);
+    let asset_0_amount = Uint128::from(222_000000u128);
+    let asset_1_amount = Uint128::from(333_000000u128);
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: asset_0_amount,
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_1_amount)],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // Store liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+
+    let res: Decimal256 =
+        from_binary(&query(deps.as_ref(), env, QueryMsg::Invariant {}).unwrap()).unwrap();
+
+    // k is the product of the raw reserves
+    assert_eq!(res, Decimal256::from_ratio(73926_000000_000000u128, 1u128));
+}
+
+#[test]
+fn test_query_impermanent_loss() {
+    let total_share_amount = Uint128::from(100u128);
+    let asset_0_amount = Uint128::from(400u128);
+    let asset_1_amount = Uint128::from(100u128);
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: asset_0_amount,
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_1_amount)],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share_amount)],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // Store liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+
+    let query_il = |entry_price: Decimal| {
+        query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::ImpermanentLoss { entry_price },
+        )
+    };
+
+    // The pool price is 0.25 = 100 / 400, entering at it loses nothing
+    let res: Decimal256 = from_binary(&query_il(Decimal::from_ratio(1u8, 4u8)).unwrap()).unwrap();
+    assert_eq!(res, Decimal256::zero());
+    // 0.2 = 1 - 2 * sqrt(0.25) / (1 + 0.25)
+    let res: Decimal256 = from_binary(&query_il(Decimal::one()).unwrap()).unwrap();
+    assert_eq!(res, Decimal256::from_ratio(1u8, 5u8));
+    assert_eq!(
+        query_il(Decimal::zero()).unwrap_err(),
+        StdError::generic_err("Entry price can not be zero")
+    );
+}
+
 #[test]
 fn test_query_share() {
     let total_share_amount = Uint128::from(500u128);
@@ -1442,6 +2400,7 @@ fn test_accumulate_prices() {
                 price0_cumulative_last: Uint128::new(case.last0),
                 price1_cumulative_last: Uint128::new(case.last1),
                 track_asset_balances: false,
+                min_commission: vec![],
             },
             Uint128::new(case.x_amount),
             Uint128::new(case.y_amount),
@@ -1510,6 +2469,41 @@ proptest! {
             commission_amount,
         ).unwrap();
     }
+
+    #[test]
+    fn simulate_and_reverse_simulate_are_consistent(
+        // Sizes are sampled on a log scale so swaps that nearly drain the ask pool are covered
+        offer_pool in (1..10u128, 6..18u32).prop_map(|(m, e)| m * 10u128.pow(e)),
+        ask_pool in (1..10u128, 6..18u32).prop_map(|(m, e)| m * 10u128.pow(e)),
+        offer_amount in (1..10u128, 0..11u32).prop_map(|(m, e)| m * 10u128.pow(e)),
+        commission_bps in prop::sample::select(vec![0u64, 30, 100, 500]),
+    ) {
+        let commission_rate = Decimal::from_ratio(commission_bps, 10_000u64);
+
+        let (return_amount, _, commission_amount) = compute_swap(
+            offer_pool.into(),
+            ask_pool.into(),
+            offer_amount.into(),
+            commission_rate,
+        ).unwrap();
+        prop_assume!(!return_amount.is_zero());
+
+        let (reverse_offer_amount, _, _) = compute_offer_amount(
+            offer_pool.into(),
+            ask_pool.into(),
+            return_amount,
+            commission_rate,
+        ).unwrap();
+
+        // Both directions round down, so the round trip may be off by a few units of the ask
+        // asset valued at the post-swap price, plus a few units of the offer asset.
+        // The commission stays in the pool, so it is part of the post-swap ask reserve.
+        let discrepancy = offer_amount.abs_diff(reverse_offer_amount.u128());
+        let ask_pool_after = ask_pool - return_amount.u128() - commission_amount.u128();
+        prop_assert!(
+            discrepancy * ask_pool_after <= 3 * (offer_pool + offer_amount) + 3 * ask_pool_after
+        );
+    }
 }
 
 #[test]
@@ -5,11 +5,12 @@ use astroport::factory::{
 };
 use astroport::pair::{
     ConfigResponse, CumulativePricesResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, QueryMsg,
-    XYKPoolConfig, XYKPoolParams, XYKPoolUpdateParams, TWAP_PRECISION,
+    ReverseSimulationResponse, SimulationResponse, TrackedHeightRangeResponse, XYKPoolConfig,
+    XYKPoolParams, XYKPoolUpdateParams, TWAP_PRECISION,
 };
 use astroport::token::InstantiateMsg as TokenInstantiateMsg;
 use astroport_pair::error::ContractError;
-use cosmwasm_std::{attr, to_binary, Addr, Coin, Decimal, Uint128};
+use cosmwasm_std::{attr, to_binary, Addr, Coin, Decimal, Decimal256, Uint128};
 use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg, MinterResponse};
 use cw_multi_test::{App, ContractWrapper, Executor};
 
@@ -281,6 +282,7 @@ fn test_provide_and_withdraw_liquidity() {
                 belief_price: None,
                 max_spread: None,
                 to: None,
+                refund_excess: false,
             },
             &[Coin {
                 denom: "cny".to_string(),
@@ -304,7 +306,8 @@ fn test_provide_and_withdraw_liquidity() {
             block_time_last: router.block_info().time.seconds(),
             params: Some(
                 to_binary(&XYKPoolConfig {
-                    track_asset_balances: false
+                    track_asset_balances: false,
+                    min_commission: vec![],
                 })
                 .unwrap()
             ),
@@ -1067,6 +1070,7 @@ fn asset_balances_tracking_works_correctly() {
         init_params: Some(
             to_binary(&XYKPoolParams {
                 track_asset_balances: Some(true),
+                min_commission: None,
             })
             .unwrap(),
         ),
@@ -1259,6 +1263,7 @@ fn asset_balances_tracking_works_correctly() {
         belief_price: None,
         max_spread: None,
         to: None,
+        refund_excess: false,
     };
     let send_funds = vec![Coin {
         denom: "uusd".to_owned(),
@@ -1337,3 +1342,387 @@ fn asset_balances_tracking_works_correctly() {
         .unwrap();
     assert_eq!(res.unwrap(), Uint128::new(499_749812));
 }
+
+#[test]
+fn tracked_height_range_follows_balances_tracking() {
+    let owner = Addr::unchecked(OWNER);
+    let mut app = mock_app(owner.clone(), vec![]);
+    let token_code_id = store_token_code(&mut app);
+    let pair_code_id = store_pair_code(&mut app);
+    let factory_code_id = store_factory_code(&mut app);
+
+    let init_msg = FactoryInstantiateMsg {
+        fee_address: None,
+        pair_configs: vec![PairConfig {
+            code_id: pair_code_id,
+            maker_fee_bps: 0,
+            pair_type: PairType::Xyk {},
+            total_fee_bps: 0,
+            is_disabled: false,
+            is_generator_disabled: false,
+        }],
+        token_code_id,
+        generator_address: Some(String::from("generator")),
+        owner: owner.to_string(),
+        whitelist_code_id: 234u64,
+        coin_registry_address: "coin_registry".to_string(),
+    };
+
+    let factory_instance = app
+        .instantiate_contract(
+            factory_code_id,
+            owner.clone(),
+            &init_msg,
+            &[],
+            "FACTORY",
+            None,
+        )
+        .unwrap();
+
+    let asset_infos = vec![
+        native_asset_info("test1".to_string()),
+        native_asset_info("test2".to_string()),
+    ];
+
+    let msg = FactoryExecuteMsg::CreatePair {
+        asset_infos: asset_infos.clone(),
+        pair_type: PairType::Xyk {},
+        init_params: None,
+    };
+    app.execute_contract(owner.clone(), factory_instance.clone(), &msg, &[])
+        .unwrap();
+
+    let res: PairInfo = app
+        .wrap()
+        .query_wasm_smart(&factory_instance, &FactoryQueryMsg::Pair { asset_infos })
+        .unwrap();
+    let pair_instance = res.contract_addr;
+
+    // Tracking is disabled
+    let res: TrackedHeightRangeResponse = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::TrackedHeightRange {})
+        .unwrap();
+    assert_eq!(
+        res,
+        TrackedHeightRangeResponse {
+            earliest: None,
+            latest: app.block_info().height,
+        }
+    );
+
+    // Enable asset balances tracking
+    let msg = ExecuteMsg::UpdateConfig {
+        params: to_binary(&XYKPoolUpdateParams::EnableAssetBalancesTracking).unwrap(),
+    };
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &[])
+        .unwrap();
+    let enabled_at = app.block_info().height;
+
+    app.update_block(|b| b.height += 10);
+
+    // Balances can be queried starting from the block after tracking was enabled
+    let res: TrackedHeightRangeResponse = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::TrackedHeightRange {})
+        .unwrap();
+    assert_eq!(
+        res,
+        TrackedHeightRangeResponse {
+            earliest: Some(enabled_at + 1),
+            latest: enabled_at + 10,
+        }
+    );
+
+    let balance_at = |app: &App, height: u64| -> Option<Uint128> {
+        app.wrap()
+            .query_wasm_smart(
+                &pair_instance,
+                &QueryMsg::AssetBalanceAt {
+                    asset_info: native_asset_info("test1".to_string()),
+                    block_height: height.into(),
+                },
+            )
+            .unwrap()
+    };
+    assert_eq!(balance_at(&app, enabled_at), None);
+    assert_eq!(balance_at(&app, enabled_at + 1), Some(Uint128::zero()));
+}
+
+#[test]
+fn zero_fee_pair_swaps_without_commission() {
+    let owner = Addr::unchecked(OWNER);
+    let user = Addr::unchecked("user");
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000_000_000u128),
+            },
+            Coin {
+                denom: "uluna".to_string(),
+                amount: Uint128::new(100_000_000_000u128),
+            },
+        ],
+    );
+    let token_code_id = store_token_code(&mut app);
+    let pair_code_id = store_pair_code(&mut app);
+    let factory_code_id = store_factory_code(&mut app);
+
+    let init_msg = FactoryInstantiateMsg {
+        fee_address: None,
+        pair_configs: vec![PairConfig {
+            code_id: pair_code_id,
+            maker_fee_bps: 0,
+            pair_type: PairType::Xyk {},
+            total_fee_bps: 0,
+            is_disabled: false,
+            is_generator_disabled: false,
+        }],
+        token_code_id,
+        generator_address: Some(String::from("generator")),
+        owner: owner.to_string(),
+        whitelist_code_id: 234u64,
+        coin_registry_address: "coin_registry".to_string(),
+    };
+
+    let factory_instance = app
+        .instantiate_contract(
+            factory_code_id,
+            owner.clone(),
+            &init_msg,
+            &[],
+            "FACTORY",
+            None,
+        )
+        .unwrap();
+
+    let asset_infos = vec![
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        AssetInfo::NativeToken {
+            denom: "uluna".to_string(),
+        },
+    ];
+
+    let msg = FactoryExecuteMsg::CreatePair {
+        asset_infos: asset_infos.clone(),
+        pair_type: PairType::Xyk {},
+        init_params: None,
+    };
+    app.execute_contract(owner.clone(), factory_instance.clone(), &msg, &[])
+        .unwrap();
+
+    let res: PairInfo = app
+        .wrap()
+        .query_wasm_smart(&factory_instance, &FactoryQueryMsg::Pair { asset_infos })
+        .unwrap();
+    let pair_instance = res.contract_addr;
+
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(100_000000),
+        Uint128::new(100_000000),
+        None,
+        None,
+    );
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    let offer_asset = Asset {
+        info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        amount: Uint128::new(1_000000),
+    };
+
+    // 990099 = 100000000 - 100000000 * 100000000 / (100000000 + 1000000)
+    // 9901 = 1000000 - 990099
+    let res: SimulationResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &pair_instance,
+            &QueryMsg::Simulation {
+                offer_asset: offer_asset.clone(),
+                ask_asset_info: None,
+                sender: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(res.return_amount, Uint128::new(990099));
+    assert_eq!(res.spread_amount, Uint128::new(9901));
+    assert_eq!(res.commission_amount, Uint128::zero());
+
+    let res: ReverseSimulationResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &pair_instance,
+            &QueryMsg::ReverseSimulation {
+                offer_asset_info: None,
+                ask_asset: Asset {
+                    info: AssetInfo::NativeToken {
+                        denom: "uluna".to_string(),
+                    },
+                    amount: Uint128::new(990099),
+                },
+                sender: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(res.offer_amount, Uint128::new(999999));
+    assert_eq!(res.commission_amount, Uint128::zero());
+
+    let msg = ExecuteMsg::Swap {
+        offer_asset: offer_asset.clone(),
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: Some(Decimal::percent(50)),
+        to: Some(user.to_string()),
+        refund_excess: false,
+    };
+    let res = app
+        .execute_contract(
+            owner.clone(),
+            pair_instance.clone(),
+            &msg,
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: offer_asset.amount,
+            }],
+        )
+        .unwrap();
+
+    let commission = res
+        .events
+        .iter()
+        .flat_map(|event| event.attributes.iter())
+        .find(|attribute| attribute.key == "commission_amount")
+        .unwrap();
+    assert_eq!(commission.value, "0");
+
+    let balance = app.wrap().query_balance(&user, "uluna").unwrap();
+    assert_eq!(balance.amount, Uint128::new(990099));
+
+    // Nothing was accrued to liquidity providers
+    let res: Vec<Asset> = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::CumulativeFees {})
+        .unwrap();
+    assert!(res.iter().all(|asset| asset.amount.is_zero()));
+}
+
+#[test]
+fn invariant_does_not_need_registered_native_coins() {
+    let owner = Addr::unchecked(OWNER);
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000_000_000u128),
+            },
+            Coin {
+                denom: "uluna".to_string(),
+                amount: Uint128::new(100_000_000_000u128),
+            },
+        ],
+    );
+    let token_code_id = store_token_code(&mut app);
+    let pair_code_id = store_pair_code(&mut app);
+    let factory_code_id = store_factory_code(&mut app);
+
+    // The coin registry only knows about uusd
+    let coin_registry_code_id = app.store_code(Box::new(ContractWrapper::new_with_empty(
+        astroport_native_coin_registry::contract::execute,
+        astroport_native_coin_registry::contract::instantiate,
+        astroport_native_coin_registry::contract::query,
+    )));
+    let coin_registry = app
+        .instantiate_contract(
+            coin_registry_code_id,
+            owner.clone(),
+            &astroport::native_coin_registry::InstantiateMsg {
+                owner: owner.to_string(),
+            },
+            &[],
+            "Coin registry",
+            None,
+        )
+        .unwrap();
+    app.execute_contract(
+        owner.clone(),
+        coin_registry.clone(),
+        &astroport::native_coin_registry::ExecuteMsg::Add {
+            native_coins: vec![("uusd".to_string(), 6)],
+        },
+        &[],
+    )
+    .unwrap();
+
+    let init_msg = FactoryInstantiateMsg {
+        fee_address: None,
+        pair_configs: vec![PairConfig {
+            code_id: pair_code_id,
+            maker_fee_bps: 0,
+            pair_type: PairType::Xyk {},
+            total_fee_bps: 30,
+            is_disabled: false,
+            is_generator_disabled: false,
+        }],
+        token_code_id,
+        generator_address: Some(String::from("generator")),
+        owner: owner.to_string(),
+        whitelist_code_id: 234u64,
+        coin_registry_address: coin_registry.to_string(),
+    };
+
+    let factory_instance = app
+        .instantiate_contract(
+            factory_code_id,
+            owner.clone(),
+            &init_msg,
+            &[],
+            "FACTORY",
+            None,
+        )
+        .unwrap();
+
+    let asset_infos = vec![
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        AssetInfo::NativeToken {
+            denom: "uluna".to_string(),
+        },
+    ];
+
+    let msg = FactoryExecuteMsg::CreatePair {
+        asset_infos: asset_infos.clone(),
+        pair_type: PairType::Xyk {},
+        init_params: None,
+    };
+    app.execute_contract(owner.clone(), factory_instance.clone(), &msg, &[])
+        .unwrap();
+
+    let res: PairInfo = app
+        .wrap()
+        .query_wasm_smart(&factory_instance, &FactoryQueryMsg::Pair { asset_infos })
+        .unwrap();
+    let pair_instance = res.contract_addr;
+
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(100_000000),
+        Uint128::new(400_000000),
+        None,
+        None,
+    );
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    let res: Decimal256 = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::Invariant {})
+        .unwrap();
+    assert_eq!(res, Decimal256::from_ratio(40000_000000_000000u128, 1u128));
+}
@@ -1,9 +1,9 @@
 use std::vec;
 
 use cosmwasm_std::{
-    attr, entry_point, from_binary, wasm_execute, wasm_instantiate, Addr, Binary, CosmosMsg,
-    Decimal, Decimal256, DepsMut, Env, MessageInfo, Reply, Response, StdError, StdResult, SubMsg,
-    SubMsgResponse, SubMsgResult, Uint128,
+    attr, entry_point, from_binary, to_binary, wasm_execute, wasm_instantiate, Addr, Binary,
+    CosmosMsg, Decimal, Decimal256, DepsMut, Env, MessageInfo, Reply, Response, StdError,
+    StdResult, SubMsg, SubMsgResponse, SubMsgResult, Uint128,
 };
 use cw2::{get_contract_version, set_contract_version};
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
@@ -17,7 +17,9 @@ use astroport::asset::{
 use astroport::common::{claim_ownership, drop_ownership_proposal, propose_new_owner};
 use astroport::cosmwasm_ext::{AbsDiff, DecimalToInteger, IntegerToDecimal};
 use astroport::factory::PairType;
-use astroport::pair::{Cw20HookMsg, ExecuteMsg, InstantiateMsg};
+use astroport::pair::{
+    execution_price, slippage_attributes, Cw20HookMsg, ExecuteMsg, InstantiateMsg, SwapResponseData,
+};
 use astroport::pair_concentrated::{
     ConcentratedPoolParams, ConcentratedPoolUpdateParams, MigrateMsg, UpdatePoolParams,
 };
@@ -29,7 +31,7 @@ use crate::math::{calc_d, get_xcp};
 use crate::migration::migrate_config;
 use crate::state::{
     store_precisions, AmpGamma, Config, PoolParams, PoolState, Precisions, PriceState, CONFIG,
-    OWNERSHIP_PROPOSAL,
+    CUMULATIVE_FEES, OWNERSHIP_PROPOSAL,
 };
 use crate::utils::{
     accumulate_prices, assert_max_spread, assert_slippage_tolerance, before_swap_check,
@@ -172,7 +174,7 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
             let mut config = CONFIG.load(deps.storage)?;
 
             if config.pair_info.liquidity_token != Addr::unchecked("") {
-                return Err(ContractError::Unauthorized {});
+                return Err(ContractError::LpTokenAlreadySet {});
             }
 
             let init_response = parse_instantiate_response_data(data.as_slice())
@@ -238,8 +240,11 @@ pub fn execute(
             belief_price,
             max_spread,
             to,
-            ..
+            refund_excess,
         } => {
+            if refund_excess {
+                return Err(ContractError::RefundExcessNotSupported {});
+            }
             offer_asset.info.check(deps.api)?;
             if !offer_asset.is_native_token() {
                 return Err(ContractError::Cw20DirectSwap {});
@@ -770,21 +775,40 @@ fn swap(
 
     CONFIG.save(deps.storage, &config)?;
 
-    Ok(Response::new().add_messages(messages).add_attributes(vec![
-        attr("action", "swap"),
-        attr("sender", sender),
-        attr("receiver", receiver),
-        attr("offer_asset", offer_asset_dec.info.to_string()),
-        attr("ask_asset", pools[ask_ind].info.to_string()),
-        attr("offer_amount", offer_asset.amount),
-        attr("return_amount", return_amount),
-        attr("spread_amount", spread_amount),
-        attr(
-            "commission_amount",
-            swap_result.total_fee.to_uint(ask_asset_prec)?,
-        ),
-        attr("maker_fee_amount", maker_fee),
-    ]))
+    let commission_amount = swap_result.total_fee.to_uint(ask_asset_prec)?;
+
+    // Accrue the liquidity providers' share of the commission
+    CUMULATIVE_FEES.update(deps.storage, &pools[ask_ind].info, |fees| -> StdResult<_> {
+        Ok(fees
+            .unwrap_or_default()
+            .saturating_add(commission_amount.saturating_sub(maker_fee)))
+    })?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            attr("action", "swap"),
+            attr("sender", sender),
+            attr("receiver", receiver),
+            attr("offer_asset", offer_asset_dec.info.to_string()),
+            attr("ask_asset", pools[ask_ind].info.to_string()),
+            attr("offer_amount", offer_asset.amount),
+            attr("return_amount", return_amount),
+            attr("spread_amount", spread_amount),
+            attr("commission_amount", commission_amount),
+            attr("maker_fee_amount", maker_fee),
+        ])
+        .add_attributes(slippage_attributes(
+            belief_price,
+            offer_asset.amount,
+            return_amount,
+        ))
+        .set_data(to_binary(&SwapResponseData {
+            return_amount,
+            spread_amount,
+            commission_amount,
+            execution_price: execution_price(offer_asset.amount, return_amount),
+        })?))
 }
 
 /// Updates the pool configuration with the specified parameters in the `params` variable.
@@ -843,3 +867,83 @@ pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, C
         .add_attribute("new_contract_name", CONTRACT_NAME)
         .add_attribute("new_contract_version", CONTRACT_VERSION))
 }
+
+#[cfg(test)]
+mod test {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{ContractResult, SystemResult, WasmQuery};
+    use cw20::{Cw20QueryMsg, TokenInfoResponse};
+
+    use super::*;
+
+    fn lp_token_reply(contract_addr: &str) -> Reply {
+        // A protobuf-encoded MsgInstantiateContractResponse holding only the contract address
+        let mut data = vec![0x0a, contract_addr.len() as u8];
+        data.extend_from_slice(contract_addr.as_bytes());
+
+        Reply {
+            id: INSTANTIATE_TOKEN_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: Some(data.into()),
+            }),
+        }
+    }
+
+    #[test]
+    fn duplicate_lp_token_reply_is_rejected() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { msg, .. } => match from_binary(msg).unwrap() {
+                Cw20QueryMsg::TokenInfo {} => SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&TokenInfoResponse {
+                        name: "Token".to_string(),
+                        symbol: "TKN".to_string(),
+                        decimals: 6,
+                        total_supply: Uint128::zero(),
+                    })
+                    .unwrap(),
+                )),
+                _ => unimplemented!(),
+            },
+            _ => unimplemented!(),
+        });
+
+        let params = ConcentratedPoolParams {
+            amp: Decimal::from_ratio(40u8, 1u8),
+            gamma: Decimal::from_ratio(145u16, 1_000_000u32),
+            mid_fee: Decimal::from_ratio(26u8, 10_000u16),
+            out_fee: Decimal::from_ratio(45u8, 10_000u16),
+            fee_gamma: Decimal::from_ratio(23u8, 100_000u32),
+            repeg_profit_threshold: Decimal::from_ratio(2u8, 1_000_000u32),
+            min_price_scale_delta: Decimal::from_ratio(146u8, 1_000_000u32),
+            price_scale: Decimal::one(),
+            ma_half_time: 600,
+        };
+        let msg = InstantiateMsg {
+            asset_infos: vec![
+                AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0000"),
+                },
+                AssetInfo::Token {
+                    contract_addr: Addr::unchecked("asset0001"),
+                },
+            ],
+            token_code_id: 10u64,
+            factory_addr: String::from("factory"),
+            init_params: Some(to_binary(&params).unwrap()),
+        };
+
+        let env = mock_env();
+        instantiate(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+        // Store liquidity token
+        reply(deps.as_mut(), env.clone(), lp_token_reply("liquidity0000")).unwrap();
+
+        let err = reply(deps.as_mut(), env, lp_token_reply("liquidity0001")).unwrap_err();
+        assert_eq!(err, ContractError::LpTokenAlreadySet {});
+
+        let pair_info = CONFIG.load(deps.as_ref().storage).unwrap().pair_info;
+        assert_eq!(Addr::unchecked("liquidity0000"), pair_info.liquidity_token);
+    }
+}
@@ -70,4 +70,10 @@ pub enum ContractError {
 
     #[error("Contract can't be migrated!")]
     MigrationError {},
+
+    #[error("LP token is already set")]
+    LpTokenAlreadySet {},
+
+    #[error("Refunding the excess of the offer asset is not supported by this pair")]
+    RefundExcessNotSupported {},
 }
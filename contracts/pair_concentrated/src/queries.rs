@@ -1,7 +1,7 @@
 use crate::contract::LP_TOKEN_PRECISION;
 use crate::error::ContractError;
 use crate::math::calc_d;
-use crate::state::{Precisions, CONFIG};
+use crate::state::{Precisions, CONFIG, CUMULATIVE_FEES};
 use crate::utils::{
     accumulate_prices, before_swap_check, calc_last_prices, compute_offer_amount, compute_swap,
     get_share_in_assets, pool_info, query_pools,
@@ -39,6 +39,11 @@ use itertools::Itertools;
 /// pool using a [`CumulativePricesResponse`] object.
 ///
 /// * **QueryMsg::Config {}** Returns the configuration for the pair contract using a [`ConfigResponse`] object.
+///
+/// * **QueryMsg::Invariant {}** Returns the current pool invariant D as a [`Decimal256`].
+///
+/// * **QueryMsg::CumulativeFees {}** Returns the swap fees accrued to liquidity providers for each asset
+/// in the pool as a vector of [`Asset`] objects.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -61,7 +66,8 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         ),
         QueryMsg::Config {} => to_binary(&query_config(deps, env)?),
         QueryMsg::LpPrice {} => to_binary(&query_lp_price(deps)?),
-        QueryMsg::ComputeD {} => to_binary(&query_compute_d(deps, env)?),
+        QueryMsg::ComputeD {} | QueryMsg::Invariant {} => to_binary(&query_compute_d(deps, env)?),
+        QueryMsg::CumulativeFees {} => to_binary(&query_cumulative_fees(deps)?),
     }
 }
 
@@ -215,6 +221,23 @@ fn query_cumulative_prices(
     })
 }
 
+/// Returns the total amount of swap fees accrued to liquidity providers for each asset in the pool.
+pub fn query_cumulative_fees(deps: Deps) -> StdResult<Vec<Asset>> {
+    let config = CONFIG.load(deps.storage)?;
+
+    config
+        .pair_info
+        .asset_infos
+        .into_iter()
+        .map(|info| {
+            let amount = CUMULATIVE_FEES
+                .may_load(deps.storage, &info)?
+                .unwrap_or_default();
+            Ok(Asset { info, amount })
+        })
+        .collect()
+}
+
 /// Compute the current LP token virtual price.
 pub fn query_lp_price(deps: Deps) -> StdResult<Decimal256> {
     let config = CONFIG.load(deps.storage)?;
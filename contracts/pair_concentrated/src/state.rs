@@ -410,6 +410,9 @@ const PRECISIONS: Map<String, u8> = Map::new("precisions");
 /// Stores the latest contract ownership transfer proposal
 pub const OWNERSHIP_PROPOSAL: Item<OwnershipProposal> = Item::new("ownership_proposal");
 
+/// Stores the total amount of swap fees accrued to liquidity providers for each asset
+pub const CUMULATIVE_FEES: Map<&AssetInfo, Uint128> = Map::new("cumulative_fees");
+
 #[cfg(test)]
 mod test {
     use std::str::FromStr;
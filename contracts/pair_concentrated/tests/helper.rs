@@ -328,6 +328,7 @@ impl Helper {
                     belief_price: None,
                     max_spread,
                     to: None,
+                    refund_excess: false,
                 };
 
                 self.app
@@ -492,6 +493,12 @@ impl Helper {
             .wrap()
             .query_wasm_smart(&self.pair_addr, &QueryMsg::ComputeD {})
     }
+
+    pub fn query_invariant(&self) -> StdResult<Decimal256> {
+        self.app
+            .wrap()
+            .query_wasm_smart(&self.pair_addr, &QueryMsg::Invariant {})
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -1,11 +1,11 @@
-use cosmwasm_std::{Addr, Decimal};
-use cw_multi_test::{next_block, Executor};
+use cosmwasm_std::{coin, Addr, Decimal, Uint128};
+use cw_multi_test::{next_block, AppResponse, Executor};
 use itertools::Itertools;
 
-use astroport::asset::{native_asset_info, AssetInfoExt, MINIMUM_LIQUIDITY_AMOUNT};
+use astroport::asset::{native_asset_info, Asset, AssetInfoExt, MINIMUM_LIQUIDITY_AMOUNT};
 use astroport::pair::ExecuteMsg;
 use astroport::pair_concentrated::{
-    ConcentratedPoolParams, ConcentratedPoolUpdateParams, PromoteParams, UpdatePoolParams,
+    ConcentratedPoolParams, ConcentratedPoolUpdateParams, PromoteParams, QueryMsg, UpdatePoolParams,
 };
 use astroport_pair_concentrated::consts::{AMP_MAX, AMP_MIN, MA_HALF_TIME_LIMITS};
 use astroport_pair_concentrated::error::ContractError;
@@ -542,6 +542,28 @@ fn check_swaps_simple() {
     let d = helper.query_d().unwrap();
     assert_eq!(dec_to_f64(d), 200000f64);
 
+    // Refunding over-sent native tokens is only supported by the XYK pair
+    let err = helper
+        .app
+        .execute_contract(
+            user.clone(),
+            helper.pair_addr.clone(),
+            &ExecuteMsg::Swap {
+                offer_asset: offer_asset.clone(),
+                ask_asset_info: None,
+                belief_price: None,
+                max_spread: None,
+                to: None,
+                refund_excess: true,
+            },
+            &[coin(100_000000u128, "uluna")],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::RefundExcessNotSupported {},
+        err.downcast().unwrap()
+    );
+
     helper.swap(&user, &offer_asset, None).unwrap();
     assert_eq!(0, helper.coin_balance(&test_coins[0], &user));
     assert_eq!(99_737929, helper.coin_balance(&test_coins[1], &user));
@@ -955,7 +977,7 @@ fn query_d_test() {
         ma_half_time: 600,
     };
     // create pair with test_coins
-    let helper = Helper::new(&owner, test_coins.clone(), params).unwrap();
+    let mut helper = Helper::new(&owner, test_coins.clone(), params).unwrap();
 
     // query current pool D value before providing any liquidity
     let err = helper.query_d().unwrap_err();
@@ -963,4 +985,93 @@ fn query_d_test() {
         err.to_string(),
         "Generic error: Querier contract error: Generic error: Pools are empty"
     );
+
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(100_000_000000u128),
+    ];
+    helper.give_me_money(&assets, &owner);
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    // The generic Invariant query returns the same D
+    let d = helper.query_d().unwrap();
+    assert!(!d.is_zero());
+    assert_eq!(helper.query_invariant().unwrap(), d);
+}
+
+#[test]
+fn swaps_accrue_cumulative_fees() {
+    let owner = Addr::unchecked("owner");
+
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::cw20("USDC")];
+
+    let params = ConcentratedPoolParams {
+        amp: f64_to_dec(40f64),
+        gamma: f64_to_dec(0.000145),
+        mid_fee: f64_to_dec(0.0026),
+        out_fee: f64_to_dec(0.0045),
+        fee_gamma: f64_to_dec(0.00023),
+        repeg_profit_threshold: f64_to_dec(0.000002),
+        min_price_scale_delta: f64_to_dec(0.000146),
+        price_scale: Decimal::one(),
+        ma_half_time: 600,
+    };
+    let mut helper = Helper::new(&owner, test_coins.clone(), params).unwrap();
+
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(100_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    let query_fees = |helper: &Helper| -> Vec<Asset> {
+        helper
+            .app
+            .wrap()
+            .query_wasm_smart(&helper.pair_addr, &QueryMsg::CumulativeFees {})
+            .unwrap()
+    };
+    assert!(query_fees(&helper)
+        .iter()
+        .all(|asset| asset.amount.is_zero()));
+
+    let attribute_of = |res: &AppResponse, key: &str| -> Uint128 {
+        res.events
+            .iter()
+            .flat_map(|event| event.attributes.iter())
+            .find(|attribute| attribute.key == key)
+            .map(|attribute| attribute.value.parse().unwrap())
+            .unwrap()
+    };
+
+    let user = Addr::unchecked("user");
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(100_000000u128);
+    helper.give_me_money(&[offer_asset.clone()], &user);
+    let res = helper.swap(&user, &offer_asset, None).unwrap();
+
+    // Only the LP share of the commission is accrued, the maker fee is sent out
+    let maker_fee = attribute_of(&res, "maker_fee_amount");
+    assert!(!maker_fee.is_zero());
+    let lp_fee = attribute_of(&res, "commission_amount") - maker_fee;
+    assert_eq!(
+        query_fees(&helper),
+        vec![
+            helper.assets[&test_coins[0]].with_balance(0u128),
+            helper.assets[&test_coins[1]].with_balance(lp_fee),
+        ]
+    );
+
+    // Swapping the other way accrues fees in the other asset
+    let offer_asset = helper.assets[&test_coins[1]].with_balance(100_000000u128);
+    helper.give_me_money(&[offer_asset.clone()], &user);
+    let res = helper.swap(&user, &offer_asset, None).unwrap();
+    let reverse_lp_fee =
+        attribute_of(&res, "commission_amount") - attribute_of(&res, "maker_fee_amount");
+    assert_eq!(
+        query_fees(&helper),
+        vec![
+            helper.assets[&test_coins[0]].with_balance(reverse_lp_fee),
+            helper.assets[&test_coins[1]].with_balance(lp_fee),
+        ]
+    );
 }
@@ -21,14 +21,14 @@ use astroport::asset::{
 use astroport::common::{claim_ownership, drop_ownership_proposal, propose_new_owner};
 use astroport::factory::PairType;
 use astroport::pair::{
-    migration_check, ConfigResponse, InstantiateMsg, StablePoolParams, StablePoolUpdateParams,
-    DEFAULT_SLIPPAGE, MAX_ALLOWED_SLIPPAGE,
+    execution_price, migration_check, slippage_attributes, ConfigResponse, InstantiateMsg,
+    StablePoolParams, StablePoolUpdateParams, DEFAULT_SLIPPAGE, MAX_ALLOWED_SLIPPAGE,
 };
 
 use crate::migration::migrate_config_to_v210;
 use astroport::pair::{
     CumulativePricesResponse, Cw20HookMsg, ExecuteMsg, MigrateMsg, PoolResponse, QueryMsg,
-    ReverseSimulationResponse, SimulationResponse, StablePoolConfig,
+    ReverseSimulationResponse, SimulationResponse, StablePoolConfig, SwapResponseData,
 };
 use astroport::querier::{
     query_factory_config, query_fee_info, query_supply, query_token_precision,
@@ -40,7 +40,10 @@ use crate::error::ContractError;
 use crate::math::{
     calc_y, compute_d, AMP_PRECISION, MAX_AMP, MAX_AMP_CHANGE, MIN_AMP_CHANGING_TIME,
 };
-use crate::state::{get_precision, store_precisions, Config, CONFIG, OWNERSHIP_PROPOSAL};
+use crate::state::{
+    get_precision, store_precisions, Config, CONFIG, CUMULATIVE_FEES, MAX_FEE_EXEMPT,
+    OWNERSHIP_PROPOSAL,
+};
 use crate::utils::{
     accumulate_prices, adjust_precision, check_asset_infos, check_assets, check_cw20_in_pool,
     compute_current_amp, compute_swap, get_share_in_assets, mint_liquidity_token_message,
@@ -116,6 +119,7 @@ pub fn instantiate(
         next_amp_time: env.block.time.seconds(),
         greatest_precision,
         cumulative_prices,
+        fee_exempt: vec![],
     };
 
     CONFIG.save(deps.storage, &config)?;
@@ -160,7 +164,7 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
             let mut config = CONFIG.load(deps.storage)?;
 
             if config.pair_info.liquidity_token != Addr::unchecked("") {
-                return Err(ContractError::Unauthorized {});
+                return Err(ContractError::LpTokenAlreadySet {});
             }
 
             let init_response = parse_instantiate_response_data(data.as_slice())
@@ -225,8 +229,11 @@ pub fn execute(
             belief_price,
             max_spread,
             to,
-            ..
+            refund_excess,
         } => {
+            if refund_excess {
+                return Err(ContractError::RefundExcessNotSupported {});
+            }
             offer_asset.info.check(deps.api)?;
             if !offer_asset.is_native_token() {
                 return Err(ContractError::Cw20DirectSwap {});
@@ -670,7 +677,12 @@ pub fn swap(
         &config.factory_addr,
         config.pair_info.pair_type.clone(),
     )?;
-    let commission_amount = fee_info.total_fee_rate.checked_mul_uint128(return_amount)?;
+    // Fee exempt swappers (e.g. designated arbitrageurs) don't pay commission
+    let commission_amount = if config.fee_exempt.contains(&sender) {
+        Uint128::zero()
+    } else {
+        fee_info.total_fee_rate.checked_mul_uint128(return_amount)?
+    };
     let return_amount = return_amount.saturating_sub(commission_amount);
 
     // Check the max spread limit (if it was specified)
@@ -705,6 +717,13 @@ pub fn swap(
         }
     }
 
+    // Accrue the liquidity providers' share of the commission
+    CUMULATIVE_FEES.update(deps.storage, &ask_pool.info, |fees| -> StdResult<_> {
+        Ok(fees
+            .unwrap_or_default()
+            .saturating_add(commission_amount - maker_fee_amount))
+    })?;
+
     if accumulate_prices(deps.as_ref(), env, &mut config, &pools)? {
         CONFIG.save(deps.storage, &config)?;
     }
@@ -726,7 +745,18 @@ pub fn swap(
             attr("spread_amount", spread_amount),
             attr("commission_amount", commission_amount),
             attr("maker_fee_amount", maker_fee_amount),
-        ]))
+        ])
+        .add_attributes(slippage_attributes(
+            belief_price,
+            offer_asset.amount,
+            return_amount,
+        ))
+        .set_data(to_binary(&SwapResponseData {
+            return_amount,
+            spread_amount,
+            commission_amount,
+            execution_price: execution_price(offer_asset.amount, return_amount),
+        })?))
 }
 
 /// Calculates the amount of fees the Maker contract gets according to specified pair parameters.
@@ -763,15 +793,21 @@ pub fn calculate_maker_fee(
 /// * **QueryMsg::Share { amount }** Returns the amount of assets that could be withdrawn from the pool
 /// using a specific amount of LP tokens. The result is returned in a vector that contains objects of type [`Asset`].
 ///
-/// * **QueryMsg::Simulation { offer_asset }** Returns the result of a swap simulation using a [`SimulationResponse`] object.
+/// * **QueryMsg::Simulation { offer_asset, sender }** Returns the result of a swap simulation using a [`SimulationResponse`] object.
+/// Fee exempt senders are quoted without commission.
 ///
-/// * **QueryMsg::ReverseSimulation { ask_asset }** Returns the result of a reverse swap simulation using
-/// a [`ReverseSimulationResponse`] object.
+/// * **QueryMsg::ReverseSimulation { ask_asset, sender }** Returns the result of a reverse swap simulation using
+/// a [`ReverseSimulationResponse`] object. Fee exempt senders are quoted without commission.
 ///
 /// * **QueryMsg::CumulativePrices {}** Returns information about cumulative prices for the assets in the
 /// pool using a [`CumulativePricesResponse`] object.
 ///
 /// * **QueryMsg::Config {}** Returns the configuration for the pair contract using a [`ConfigResponse`] object.
+///
+/// * **QueryMsg::Invariant {}** Returns the current pool invariant D as a [`Decimal256`].
+///
+/// * **QueryMsg::CumulativeFees {}** Returns the total amount of swap fees accrued to liquidity providers
+/// for each asset in the pool.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -781,19 +817,30 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::Simulation {
             offer_asset,
             ask_asset_info,
-        } => to_binary(&query_simulation(deps, env, offer_asset, ask_asset_info)?),
+            sender,
+        } => to_binary(&query_simulation(
+            deps,
+            env,
+            offer_asset,
+            ask_asset_info,
+            sender,
+        )?),
         QueryMsg::ReverseSimulation {
             offer_asset_info,
             ask_asset,
+            sender,
         } => to_binary(&query_reverse_simulation(
             deps,
             env,
             ask_asset,
             offer_asset_info,
+            sender,
         )?),
         QueryMsg::CumulativePrices {} => to_binary(&query_cumulative_prices(deps, env)?),
         QueryMsg::Config {} => to_binary(&query_config(deps, env)?),
         QueryMsg::QueryComputeD {} => to_binary(&query_compute_d(deps, env)?),
+        QueryMsg::Invariant {} => to_binary(&query_invariant(deps, env)?),
+        QueryMsg::CumulativeFees {} => to_binary(&query_cumulative_fees(deps)?),
         _ => Err(StdError::generic_err("Query is not supported")),
     }
 }
@@ -812,6 +859,23 @@ pub fn query_pool(deps: Deps) -> StdResult<PoolResponse> {
     Ok(resp)
 }
 
+/// Returns the total amount of swap fees accrued to liquidity providers for each asset in the pool.
+pub fn query_cumulative_fees(deps: Deps) -> StdResult<Vec<Asset>> {
+    let config = CONFIG.load(deps.storage)?;
+
+    config
+        .pair_info
+        .asset_infos
+        .into_iter()
+        .map(|info| {
+            let amount = CUMULATIVE_FEES
+                .may_load(deps.storage, &info)?
+                .unwrap_or_default();
+            Ok(Asset { info, amount })
+        })
+        .collect()
+}
+
 /// Returns the amount of assets that could be withdrawn from the pool using a specific amount of LP tokens.
 /// The result is returned in a vector that contains objects of type [`Asset`].
 ///
@@ -827,11 +891,14 @@ pub fn query_share(deps: Deps, amount: Uint128) -> StdResult<Vec<Asset>> {
 /// Returns information about a swap simulation in a [`SimulationResponse`] object.
 ///
 /// * **offer_asset** is the asset to swap as well as an amount of the said asset.
+///
+/// * **sender** is the address that would execute the swap. Fee exempt senders are quoted without commission.
 pub fn query_simulation(
     deps: Deps,
     env: Env,
     offer_asset: Asset,
     ask_asset_info: Option<AssetInfo>,
+    sender: Option<String>,
 ) -> StdResult<SimulationResponse> {
     let config = CONFIG.load(deps.storage)?;
     let pools = config.pair_info.query_pools_decimal(
@@ -886,7 +953,12 @@ pub fn query_simulation(
         config.pair_info.pair_type.clone(),
     )?;
 
-    let commission_amount = fee_info.total_fee_rate.checked_mul_uint128(return_amount)?;
+    let commission_rate = if is_fee_exempt(deps, &config, sender)? {
+        Decimal::zero()
+    } else {
+        fee_info.total_fee_rate
+    };
+    let commission_amount = commission_rate.checked_mul_uint128(return_amount)?;
     let return_amount = return_amount.saturating_sub(commission_amount);
 
     Ok(SimulationResponse {
@@ -903,11 +975,14 @@ pub fn query_simulation(
 ///
 /// * **offer_asset_info** is optional field which specifies the asset to swap from.
 /// May be omitted only in case the pool length is 2.
+///
+/// * **sender** is the address that would execute the swap. Fee exempt senders are quoted without commission.
 pub fn query_reverse_simulation(
     deps: Deps,
     env: Env,
     ask_asset: Asset,
     offer_asset_info: Option<AssetInfo>,
+    sender: Option<String>,
 ) -> StdResult<ReverseSimulationResponse> {
     let config = CONFIG.load(deps.storage)?;
     let pools = config.pair_info.query_pools_decimal(
@@ -948,11 +1023,15 @@ pub fn query_reverse_simulation(
         &config.factory_addr,
         config.pair_info.pair_type.clone(),
     )?;
-    let before_commission = (Decimal256::one()
-        - Decimal256::new(fee_info.total_fee_rate.atomics().into()))
-    .inv()
-    .ok_or_else(|| StdError::generic_err("The pool must have less than 100% fee!"))?
-    .checked_mul(Decimal256::with_precision(ask_asset.amount, ask_precision)?)?;
+    let commission_rate = if is_fee_exempt(deps, &config, sender)? {
+        Decimal::zero()
+    } else {
+        fee_info.total_fee_rate
+    };
+    let before_commission = (Decimal256::one() - Decimal256::new(commission_rate.atomics().into()))
+        .inv()
+        .ok_or_else(|| StdError::generic_err("The pool must have less than 100% fee!"))?
+        .checked_mul(Decimal256::with_precision(ask_asset.amount, ask_precision)?)?;
 
     let xp = pools.into_iter().map(|pool| pool.amount).collect_vec();
     let new_offer_pool_amount = calc_y(
@@ -973,8 +1052,7 @@ pub fn query_reverse_simulation(
         offer_amount,
         spread_amount: offer_amount
             .saturating_sub(before_commission.to_uint128_with_precision(offer_precision)?),
-        commission_amount: fee_info
-            .total_fee_rate
+        commission_amount: commission_rate
             .checked_mul_uint128(before_commission.to_uint128_with_precision(ask_precision)?)?,
     })
 }
@@ -1009,6 +1087,7 @@ pub fn query_config(deps: Deps, env: Env) -> StdResult<ConfigResponse> {
         block_time_last: config.block_time_last,
         params: Some(to_binary(&StablePoolConfig {
             amp: Decimal::from_ratio(compute_current_amp(&config, &env)?, AMP_PRECISION),
+            fee_exempt: config.fee_exempt.clone(),
         })?),
         owner: config.owner,
     })
@@ -1124,6 +1203,9 @@ pub fn update_config(
             next_amp_time,
         } => start_changing_amp(config, deps, env, next_amp, next_amp_time)?,
         StablePoolUpdateParams::StopChangingAmp {} => stop_changing_amp(config, deps, env)?,
+        StablePoolUpdateParams::UpdateFeeExempt { add, remove } => {
+            update_fee_exempt(config, deps, add, remove)?
+        }
     }
 
     Ok(Response::default())
@@ -1188,10 +1270,59 @@ fn stop_changing_amp(mut config: Config, deps: DepsMut, env: Env) -> StdResult<(
 
     Ok(())
 }
+
+/// Updates the list of swappers that don't pay commission.
+/// The list can't grow beyond [`MAX_FEE_EXEMPT`] addresses.
+///
+/// * **add** addresses to add to the list.
+///
+/// * **remove** addresses to remove from the list.
+fn update_fee_exempt(
+    mut config: Config,
+    deps: DepsMut,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<(), ContractError> {
+    let remove = remove
+        .iter()
+        .map(|addr| deps.api.addr_validate(addr))
+        .collect::<StdResult<Vec<_>>>()?;
+    config.fee_exempt.retain(|addr| !remove.contains(addr));
+
+    for addr in add {
+        let addr = deps.api.addr_validate(&addr)?;
+        if !config.fee_exempt.contains(&addr) {
+            config.fee_exempt.push(addr);
+        }
+    }
+
+    if config.fee_exempt.len() > MAX_FEE_EXEMPT {
+        return Err(ContractError::FeeExemptLimitExceeded(MAX_FEE_EXEMPT));
+    }
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(())
+}
+
+/// Returns whether the address that would execute a simulated swap is exempt from commission.
+fn is_fee_exempt(deps: Deps, config: &Config, sender: Option<String>) -> StdResult<bool> {
+    Ok(addr_opt_validate(deps.api, &sender)?
+        .map(|sender| config.fee_exempt.contains(&sender))
+        .unwrap_or(false))
+}
+
 /// Compute the current pool D value.
 fn query_compute_d(deps: Deps, env: Env) -> StdResult<Uint128> {
     let config = CONFIG.load(deps.storage)?;
 
+    query_invariant(deps, env)?.to_uint128_with_precision(config.greatest_precision)
+}
+
+/// Returns the current D invariant of the pool as a [`Decimal256`] value.
+fn query_invariant(deps: Deps, env: Env) -> StdResult<Decimal256> {
+    let config = CONFIG.load(deps.storage)?;
+
     let amp = compute_current_amp(&config, &env)?;
     let pools = config
         .pair_info
@@ -1200,7 +1331,5 @@ fn query_compute_d(deps: Deps, env: Env) -> StdResult<Uint128> {
         .map(|pool| pool.amount)
         .collect::<Vec<_>>();
 
-    compute_d(amp, &pools)
-        .map_err(|_| StdError::generic_err("Failed to calculate the D"))?
-        .to_uint128_with_precision(config.greatest_precision)
+    compute_d(amp, &pools).map_err(|_| StdError::generic_err("Failed to calculate the D"))
 }
@@ -88,6 +88,15 @@ pub enum ContractError {
 
     #[error("Failed to parse or process reply message")]
     FailedToParseReply {},
+
+    #[error("LP token is already set")]
+    LpTokenAlreadySet {},
+
+    #[error("The fee exempt list can't hold more than {0} addresses")]
+    FeeExemptLimitExceeded(usize),
+
+    #[error("Refunding the excess of the offer asset is not supported by this pair")]
+    RefundExcessNotSupported {},
 }
 
 impl From<OverflowError> for ContractError {
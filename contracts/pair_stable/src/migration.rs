@@ -85,6 +85,7 @@ pub fn migrate_config_to_v210(mut deps: DepsMut) -> StdResult<Config> {
         next_amp_time: cfg_v100.next_amp_time,
         greatest_precision,
         cumulative_prices,
+        fee_exempt: vec![],
     };
 
     CONFIG.save(deps.storage, &cfg)?;
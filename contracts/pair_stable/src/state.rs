@@ -27,16 +27,26 @@ pub struct Config {
     pub greatest_precision: u8,
     /// The vector contains cumulative prices for each pair of assets in the pool
     pub cumulative_prices: Vec<(AssetInfo, AssetInfo, Uint128)>,
+    /// Addresses that are not charged commission on swaps
+    #[serde(default)]
+    pub fee_exempt: Vec<Addr>,
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
 
+/// The maximum number of fee exempt swappers. The list is loaded with the config on every
+/// operation, so it is kept short.
+pub const MAX_FEE_EXEMPT: usize = 10;
+
 /// Stores map of AssetInfo (as String) -> precision
 const PRECISIONS: Map<String, u8> = Map::new("precisions");
 
 /// Stores the latest contract ownership transfer proposal
 pub const OWNERSHIP_PROPOSAL: Item<OwnershipProposal> = Item::new("ownership_proposal");
 
+/// Stores the total amount of swap fees accrued to liquidity providers for each asset
+pub const CUMULATIVE_FEES: Map<&AssetInfo, Uint128> = Map::new("cumulative_fees");
+
 /// Store all token precisions and return the greatest one.
 pub(crate) fn store_precisions(
     deps: DepsMut,
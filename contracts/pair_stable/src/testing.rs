@@ -31,7 +31,7 @@ struct MsgInstantiateContractResponse {
     pub data: Vec<u8>,
 }
 
-fn store_liquidity_token(deps: DepsMut, msg_id: u64, contract_addr: String) {
+fn lp_token_reply(msg_id: u64, contract_addr: String) -> Reply {
     let instantiate_reply = MsgInstantiateContractResponse {
         contract_address: contract_addr,
         data: vec![],
@@ -42,15 +42,17 @@ fn store_liquidity_token(deps: DepsMut, msg_id: u64, contract_addr: String) {
         .encode(&mut encoded_instantiate_reply)
         .unwrap();
 
-    let reply_msg = Reply {
+    Reply {
         id: msg_id,
         result: SubMsgResult::Ok(SubMsgResponse {
             events: vec![],
             data: Some(encoded_instantiate_reply.into()),
         }),
-    };
+    }
+}
 
-    reply(deps, mock_env(), reply_msg).unwrap();
+fn store_liquidity_token(deps: DepsMut, msg_id: u64, contract_addr: String) {
+    reply(deps, mock_env(), lp_token_reply(msg_id, contract_addr)).unwrap();
 }
 
 #[test]
@@ -134,6 +136,54 @@ fn proper_initialization() {
     );
 }
 
+#[test]
+fn duplicate_lp_token_reply_is_rejected() {
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(123u128))],
+    )]);
+
+    let msg = InstantiateMsg {
+        factory_addr: String::from("factory"),
+        asset_infos: vec![
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: Addr::unchecked("asset0000"),
+            },
+        ],
+        token_code_id: 10u64,
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                owner: None,
+            })
+            .unwrap(),
+        ),
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // Store liquidity token
+    store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
+
+    let err = reply(
+        deps.as_mut(),
+        env,
+        lp_token_reply(1, "liquidity0001".to_string()),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::LpTokenAlreadySet {});
+
+    let pair_info = CONFIG.load(deps.as_ref().storage).unwrap().pair_info;
+    assert_eq!(Addr::unchecked("liquidity0000"), pair_info.liquidity_token);
+}
+
 #[test]
 fn provide_liquidity() {
     let mut deps = mock_dependencies(&[Coin {
@@ -695,6 +745,30 @@ fn try_native_to_token() {
     // Store the liquidity token
     store_liquidity_token(deps.as_mut(), 1, "liquidity0000".to_string());
 
+    // Refunding the excess is not supported by stableswap pairs
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: Some(Decimal::percent(50)),
+        to: None,
+        refund_excess: true,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount + Uint128::new(1000),
+        }],
+    );
+    let err = execute(deps.as_mut(), mock_env_with_block_time(1000), info, msg).unwrap_err();
+    assert_eq!(err, ContractError::RefundExcessNotSupported {});
+
     // Normal swap
     let msg = ExecuteMsg::Swap {
         offer_asset: Asset {
@@ -707,6 +781,7 @@ fn try_native_to_token() {
         belief_price: None,
         max_spread: Some(Decimal::percent(50)),
         to: None,
+        refund_excess: false,
     };
     let env = mock_env_with_block_time(1000);
     let info = mock_info(
@@ -756,6 +831,7 @@ fn try_native_to_token() {
             amount: offer_amount,
         },
         None,
+        None,
     )
     .unwrap();
     assert!(expected_return_amount.abs_diff(simulation_res.return_amount) <= Uint128::one());
@@ -863,6 +939,7 @@ fn try_token_to_native() {
         belief_price: None,
         max_spread: None,
         to: None,
+        refund_excess: false,
     };
     let env = mock_env_with_block_time(1000);
     let info = mock_info("addr0000", &[]);
@@ -927,6 +1004,7 @@ fn try_token_to_native() {
             },
         },
         None,
+        None,
     )
     .unwrap();
     assert!(expected_return_amount.abs_diff(simulation_res.return_amount) <= Uint128::one());
@@ -1255,6 +1333,7 @@ fn test_accumulate_prices() {
             next_amp_time: env.block.time.seconds(),
             greatest_precision: 6,
             cumulative_prices,
+            fee_exempt: vec![],
         };
 
         let pools = pools
@@ -1359,6 +1438,7 @@ proptest! {
             env.clone(),
             native_asset("uluna".to_string(), result.return_amount),
             None,
+            None,
         )
         .unwrap();
 
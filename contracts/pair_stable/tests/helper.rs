@@ -297,6 +297,7 @@ impl Helper {
                     belief_price: None,
                     max_spread: None,
                     to: None,
+                    refund_excess: false,
                 };
 
                 self.app
@@ -315,6 +316,7 @@ impl Helper {
             &QueryMsg::Simulation {
                 offer_asset: offer_asset.clone(),
                 ask_asset_info,
+                sender: None,
             },
         )
     }
@@ -329,6 +331,7 @@ impl Helper {
             &QueryMsg::ReverseSimulation {
                 ask_asset: ask_asset.clone(),
                 offer_asset_info,
+                sender: None,
             },
         )
     }
@@ -1,17 +1,20 @@
-use astroport::asset::{Asset, AssetInfo, PairInfo};
+use astroport::asset::{Asset, AssetInfo, AssetInfoExt, PairInfo};
+use astroport::cosmwasm_ext::DecimalToInteger;
 use astroport::factory::{
     ExecuteMsg as FactoryExecuteMsg, InstantiateMsg as FactoryInstantiateMsg, PairConfig, PairType,
     QueryMsg as FactoryQueryMsg,
 };
 use astroport::pair::{
     ConfigResponse, CumulativePricesResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, QueryMsg,
-    StablePoolConfig, StablePoolParams, StablePoolUpdateParams, TWAP_PRECISION,
+    ReverseSimulationResponse, SimulationResponse, StablePoolConfig, StablePoolParams,
+    StablePoolUpdateParams, TWAP_PRECISION,
 };
 
 use astroport::token::InstantiateMsg as TokenInstantiateMsg;
 use astroport_pair_stable::math::{MAX_AMP, MAX_AMP_CHANGE, MIN_AMP_CHANGING_TIME};
+use astroport_pair_stable::state::MAX_FEE_EXEMPT;
 use cosmwasm_std::{
-    attr, from_binary, to_binary, Addr, Coin, Decimal, QueryRequest, Uint128, WasmQuery,
+    attr, from_binary, to_binary, Addr, Coin, Decimal, Decimal256, QueryRequest, Uint128, WasmQuery,
 };
 use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg, MinterResponse};
 use cw_multi_test::{App, ContractWrapper, Executor};
@@ -101,7 +104,12 @@ fn instantiate_coin_registry(mut app: &mut App, coins: Option<Vec<(String, u8)>>
     coin_registry_address
 }
 
-fn instantiate_pair(mut router: &mut App, owner: &Addr) -> Addr {
+fn instantiate_pair(router: &mut App, owner: &Addr) -> Addr {
+    instantiate_pair_with_factory(router, owner).0
+}
+
+/// Instantiates a stableswap pair and returns its address along with the address of its factory
+fn instantiate_pair_with_factory(mut router: &mut App, owner: &Addr) -> (Addr, Addr) {
     let coin_registry_address = instantiate_coin_registry(
         &mut router,
         Some(vec![("uusd".to_string(), 6), ("uluna".to_string(), 6)]),
@@ -206,7 +214,7 @@ fn instantiate_pair(mut router: &mut App, owner: &Addr) -> Addr {
     assert_eq!("contract2", res.contract_addr);
     assert_eq!("contract3", res.liquidity_token);
 
-    pair
+    (pair, factory_addr)
 }
 
 #[test]
@@ -923,6 +931,12 @@ fn test_compatibility_of_tokens_with_different_precision() {
         .unwrap();
     assert_eq!(d, 20000000000000);
 
+    let invariant: Decimal256 = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::Invariant {})
+        .unwrap();
+    assert_eq!(invariant.to_uint(7u8).unwrap().u128(), d);
+
     let user = Addr::unchecked("user");
 
     let msg = Cw20ExecuteMsg::Send {
@@ -956,6 +970,12 @@ fn test_compatibility_of_tokens_with_different_precision() {
         .query_wasm_smart(&pair_instance, &QueryMsg::QueryComputeD {})
         .unwrap();
     assert_eq!(d, 19999999999999);
+
+    let invariant: Decimal256 = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::Invariant {})
+        .unwrap();
+    assert_eq!(invariant.to_uint(7u8).unwrap().u128(), d);
 }
 
 #[test]
@@ -1346,3 +1366,358 @@ fn update_pair_config() {
 
     assert_eq!(params.amp, Decimal::from_ratio(150u32, 1u32));
 }
+
+#[test]
+fn fee_exempt_swapper_pays_no_commission() {
+    let owner = Addr::unchecked(OWNER);
+    let arbitrageur = Addr::unchecked("arbitrageur");
+    let trader = Addr::unchecked("trader");
+
+    let mut router = mock_app(
+        owner.clone(),
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000_000_000u128),
+            },
+            Coin {
+                denom: "uluna".to_string(),
+                amount: Uint128::new(100_000_000_000u128),
+            },
+        ],
+    );
+
+    for swapper in [&arbitrageur, &trader] {
+        router
+            .send_tokens(
+                owner.clone(),
+                swapper.clone(),
+                &[Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::new(1_000_000u128),
+                }],
+            )
+            .unwrap();
+    }
+
+    let pair_instance = instantiate_pair(&mut router, &owner);
+
+    let (msg, coins) =
+        provide_liquidity_msg(Uint128::new(100_000_000), Uint128::new(100_000_000), None);
+    router
+        .execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    let msg = ExecuteMsg::UpdateConfig {
+        params: to_binary(&StablePoolUpdateParams::UpdateFeeExempt {
+            add: vec![arbitrageur.to_string()],
+            remove: vec![],
+        })
+        .unwrap(),
+    };
+
+    // Only the owner can update the fee exempt list
+    let err = router
+        .execute_contract(trader.clone(), pair_instance.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "Unauthorized");
+
+    router
+        .execute_contract(owner.clone(), pair_instance.clone(), &msg, &[])
+        .unwrap();
+
+    let res: ConfigResponse = router
+        .wrap()
+        .query_wasm_smart(pair_instance.clone(), &QueryMsg::Config {})
+        .unwrap();
+    let params: StablePoolConfig = from_binary(&res.params.unwrap()).unwrap();
+    assert_eq!(params.fee_exempt, vec![arbitrageur.clone()]);
+
+    let swap_msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::new(1_000_000u128),
+        },
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        refund_excess: false,
+    };
+    let send_funds = [Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(1_000_000u128),
+    }];
+
+    let commission_of = |res: &cw_multi_test::AppResponse| {
+        res.events
+            .iter()
+            .flat_map(|event| event.attributes.iter())
+            .find(|attribute| attribute.key == "commission_amount")
+            .map(|attribute| attribute.value.clone())
+            .unwrap()
+    };
+
+    let simulate = |sender: Option<&Addr>| -> SimulationResponse {
+        router
+            .wrap()
+            .query_wasm_smart(
+                pair_instance.clone(),
+                &QueryMsg::Simulation {
+                    offer_asset: Asset {
+                        info: AssetInfo::NativeToken {
+                            denom: "uusd".to_string(),
+                        },
+                        amount: Uint128::new(1_000_000u128),
+                    },
+                    ask_asset_info: None,
+                    sender: sender.map(Addr::to_string),
+                },
+            )
+            .unwrap()
+    };
+
+    // Quotes for fee exempt swappers come without commission, anyone else is quoted the full fee
+    let exempt_simulation = simulate(Some(&arbitrageur));
+    assert_eq!(exempt_simulation.commission_amount, Uint128::zero());
+    let simulation = simulate(None);
+    assert!(!simulation.commission_amount.is_zero());
+    assert_eq!(simulate(Some(&trader)), simulation);
+    assert_eq!(
+        exempt_simulation.return_amount,
+        simulation.return_amount + simulation.commission_amount
+    );
+
+    let reverse_simulation: ReverseSimulationResponse = router
+        .wrap()
+        .query_wasm_smart(
+            pair_instance.clone(),
+            &QueryMsg::ReverseSimulation {
+                offer_asset_info: None,
+                ask_asset: Asset {
+                    info: AssetInfo::NativeToken {
+                        denom: "uluna".to_string(),
+                    },
+                    amount: exempt_simulation.return_amount,
+                },
+                sender: Some(arbitrageur.to_string()),
+            },
+        )
+        .unwrap();
+    assert_eq!(reverse_simulation.commission_amount, Uint128::zero());
+
+    let res = router
+        .execute_contract(
+            arbitrageur.clone(),
+            pair_instance.clone(),
+            &swap_msg,
+            &send_funds,
+        )
+        .unwrap();
+    assert_eq!(commission_of(&res), "0");
+    let return_amount = res
+        .events
+        .iter()
+        .flat_map(|event| event.attributes.iter())
+        .find(|attribute| attribute.key == "return_amount")
+        .map(|attribute| attribute.value.clone())
+        .unwrap();
+    assert_eq!(return_amount, exempt_simulation.return_amount.to_string());
+
+    let res = router
+        .execute_contract(
+            trader.clone(),
+            pair_instance.clone(),
+            &swap_msg,
+            &send_funds,
+        )
+        .unwrap();
+    assert_ne!(commission_of(&res), "0");
+
+    // Once removed from the list, the arbitrageur pays commission again
+    let msg = ExecuteMsg::UpdateConfig {
+        params: to_binary(&StablePoolUpdateParams::UpdateFeeExempt {
+            add: vec![],
+            remove: vec![arbitrageur.to_string()],
+        })
+        .unwrap(),
+    };
+    router
+        .execute_contract(owner.clone(), pair_instance.clone(), &msg, &[])
+        .unwrap();
+
+    let res: ConfigResponse = router
+        .wrap()
+        .query_wasm_smart(pair_instance.clone(), &QueryMsg::Config {})
+        .unwrap();
+    let params: StablePoolConfig = from_binary(&res.params.unwrap()).unwrap();
+    assert!(params.fee_exempt.is_empty());
+
+    // The list is capped as it is loaded with the config on every operation
+    let msg = ExecuteMsg::UpdateConfig {
+        params: to_binary(&StablePoolUpdateParams::UpdateFeeExempt {
+            add: (0..=MAX_FEE_EXEMPT)
+                .map(|i| format!("swapper{i}"))
+                .collect(),
+            remove: vec![],
+        })
+        .unwrap(),
+    };
+    let err = router
+        .execute_contract(owner.clone(), pair_instance.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        format!("The fee exempt list can't hold more than {MAX_FEE_EXEMPT} addresses")
+    );
+}
+
+#[test]
+fn swaps_accrue_cumulative_fees() {
+    let owner = Addr::unchecked(OWNER);
+    let arbitrageur = Addr::unchecked("arbitrageur");
+    let trader = Addr::unchecked("trader");
+
+    let mut router = mock_app(
+        owner.clone(),
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000_000_000u128),
+            },
+            Coin {
+                denom: "uluna".to_string(),
+                amount: Uint128::new(100_000_000_000u128),
+            },
+        ],
+    );
+
+    for swapper in [&arbitrageur, &trader] {
+        router
+            .send_tokens(
+                owner.clone(),
+                swapper.clone(),
+                &[Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::new(3_000_000u128),
+                }],
+            )
+            .unwrap();
+    }
+
+    let (pair_instance, factory_instance) = instantiate_pair_with_factory(&mut router, &owner);
+
+    // Set a Maker so swaps pay a maker fee
+    router
+        .execute_contract(
+            owner.clone(),
+            factory_instance,
+            &FactoryExecuteMsg::UpdateConfig {
+                token_code_id: None,
+                fee_address: Some("maker".to_string()),
+                generator_address: None,
+                whitelist_code_id: None,
+                coin_registry_address: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+    let (msg, coins) =
+        provide_liquidity_msg(Uint128::new(100_000_000), Uint128::new(100_000_000), None);
+    router
+        .execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    let msg = ExecuteMsg::UpdateConfig {
+        params: to_binary(&StablePoolUpdateParams::UpdateFeeExempt {
+            add: vec![arbitrageur.to_string()],
+            remove: vec![],
+        })
+        .unwrap(),
+    };
+    router
+        .execute_contract(owner.clone(), pair_instance.clone(), &msg, &[])
+        .unwrap();
+
+    let swap_msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::new(1_000_000u128),
+        },
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        refund_excess: false,
+    };
+    let send_funds = [Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(1_000_000u128),
+    }];
+
+    let attribute_of = |res: &cw_multi_test::AppResponse, key: &str| -> Uint128 {
+        res.events
+            .iter()
+            .flat_map(|event| event.attributes.iter())
+            .find(|attribute| attribute.key == key)
+            .map(|attribute| attribute.value.parse().unwrap())
+            .unwrap()
+    };
+    let query_cumulative_fees = |router: &App| -> Vec<Asset> {
+        router
+            .wrap()
+            .query_wasm_smart(pair_instance.clone(), &QueryMsg::CumulativeFees {})
+            .unwrap()
+    };
+
+    let mut expected_fees = Uint128::zero();
+    for _ in 0..2 {
+        let res = router
+            .execute_contract(
+                trader.clone(),
+                pair_instance.clone(),
+                &swap_msg,
+                &send_funds,
+            )
+            .unwrap();
+        let commission_amount = attribute_of(&res, "commission_amount");
+        let maker_fee_amount = attribute_of(&res, "maker_fee_amount");
+        assert!(!maker_fee_amount.is_zero());
+        assert!(maker_fee_amount < commission_amount);
+
+        // Only the liquidity providers' share of the commission is accrued
+        expected_fees += commission_amount - maker_fee_amount;
+    }
+
+    let uluna = AssetInfo::NativeToken {
+        denom: "uluna".to_string(),
+    };
+    let uusd = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+    assert_eq!(
+        query_cumulative_fees(&router),
+        vec![uusd.with_balance(0u128), uluna.with_balance(expected_fees)]
+    );
+
+    // Fee exempt swaps don't add anything
+    let res = router
+        .execute_contract(
+            arbitrageur.clone(),
+            pair_instance.clone(),
+            &swap_msg,
+            &send_funds,
+        )
+        .unwrap();
+    assert_eq!(attribute_of(&res, "commission_amount"), Uint128::zero());
+    assert_eq!(attribute_of(&res, "maker_fee_amount"), Uint128::zero());
+    assert_eq!(
+        query_cumulative_fees(&router),
+        vec![uusd.with_balance(0u128), uluna.with_balance(expected_fees)]
+    );
+}
@@ -270,6 +270,43 @@ fn check_swaps() {
     assert_eq!(99_949011, helper.coin_balance(&test_coins[1], &user));
 }
 
+#[test]
+fn simulate_and_reverse_simulate_are_consistent() {
+    let owner = Addr::unchecked("owner");
+
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::cw20("USDC")];
+
+    let mut helper = Helper::new(&owner, test_coins.clone(), 100u64, None).unwrap();
+
+    // Imbalanced pool so the round trip crosses a non-trivial part of the curve
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(60_000_000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    for amount in [1_000000u128, 100_000000, 10_000_000000] {
+        let offer_asset = helper.assets[&test_coins[0]].with_balance(amount);
+        let sim_resp = helper
+            .simulate_swap(&offer_asset, Some(helper.assets[&test_coins[1]].clone()))
+            .unwrap();
+        let reverse_sim_resp = helper
+            .simulate_reverse_swap(
+                &helper.assets[&test_coins[1]].with_balance(sim_resp.return_amount.u128()),
+                Some(helper.assets[&test_coins[0]].clone()),
+            )
+            .unwrap();
+
+        // Allow for rounding in both directions: at most 0.01% of the offer plus a few units
+        let discrepancy = amount.abs_diff(reverse_sim_resp.offer_amount.u128());
+        assert!(
+            discrepancy <= amount / 10_000 + 3,
+            "offer {amount} round-tripped to {}",
+            reverse_sim_resp.offer_amount
+        );
+    }
+}
+
 #[test]
 fn check_wrong_initializations() {
     let owner = Addr::unchecked("owner");
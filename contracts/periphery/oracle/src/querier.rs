@@ -28,6 +28,7 @@ pub fn query_prices(
         &PairQueryMsg::Simulation {
             offer_asset,
             ask_asset_info,
+            sender: None,
         },
     )
 }
@@ -351,6 +351,7 @@ fn simulate_swap_operations(
                             amount: return_amount,
                         },
                         ask_asset_info: Some(ask_asset_info.clone()),
+                        sender: None,
                     },
                 )?;
 
@@ -117,6 +117,7 @@ pub fn asset_into_swap_msg(
                     belief_price,
                     max_spread,
                     to,
+                    refund_excess: false,
                 })?,
             }))
         }
@@ -75,6 +75,7 @@ pub fn build_swap_msg(
                 belief_price: None,
                 max_spread: Some(max_spread),
                 to: None,
+                refund_excess: false,
             })?,
             funds: vec![coin],
         }))
@@ -114,6 +114,22 @@ impl Asset {
         }
     }
 
+    /// Returns the amount of native tokens sent on top of the asset amount.
+    /// Fails the same way as [`Asset::assert_sent_native_token_balance`] if less was sent.
+    pub fn sent_native_token_excess(&self, message_info: &MessageInfo) -> StdResult<Uint128> {
+        if let AssetInfo::NativeToken { denom } = &self.info {
+            let amount = must_pay(message_info, denom)
+                .map_err(|err| StdError::generic_err(err.to_string()))?;
+            amount.checked_sub(self.amount).map_err(|_| {
+                StdError::generic_err(
+                    "Native token balance mismatch between the argument and the transferred",
+                )
+            })
+        } else {
+            Ok(Uint128::zero())
+        }
+    }
+
     pub fn to_decimal_asset(&self, precision: impl Into<u32>) -> StdResult<DecimalAsset> {
         Ok(DecimalAsset {
             info: self.info.clone(),
@@ -381,7 +397,9 @@ pub fn addr_opt_validate(api: &dyn Api, addr: &Option<String>) -> StdResult<Opti
 
 const TOKEN_SYMBOL_MAX_LENGTH: usize = 4;
 
-/// Returns a formatted LP token name
+/// Returns a formatted LP token name.
+/// Pairs name their LP token with this function at instantiation, so it can also be
+/// used to preview the LP token name before a pair is created.
 pub fn format_lp_token_name(
     asset_infos: &[AssetInfo],
     querier: &QuerierWrapper,
@@ -1,8 +1,9 @@
 use crate::asset::{AssetInfo, PairInfo};
 
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Addr, Binary};
+use cosmwasm_std::{Addr, Binary, StdError, StdResult};
 use std::fmt::{Display, Formatter, Result};
+use std::str::FromStr;
 
 const MAX_TOTAL_FEE_BPS: u16 = 10_000;
 const MAX_MAKER_FEE_BPS: u16 = 10_000;
@@ -53,6 +54,24 @@ impl Display for PairType {
     }
 }
 
+/// Parses a pool type from its raw encoded name.
+/// The canonical names are `xyk` and `stable` for the built-in types and `custom-{name}`
+/// for custom ones, so `PairType::from_str(&pair_type.to_string())` always round-trips.
+impl FromStr for PairType {
+    type Err = StdError;
+
+    fn from_str(s: &str) -> StdResult<Self> {
+        match s {
+            "xyk" => Ok(PairType::Xyk {}),
+            "stable" => Ok(PairType::Stable {}),
+            _ => s
+                .strip_prefix("custom-")
+                .map(|pair_type| PairType::Custom(pair_type.to_string()))
+                .ok_or_else(|| StdError::generic_err(format!("Unknown pair type: {s}"))),
+        }
+    }
+}
+
 /// This structure stores a pair type's configuration.
 #[cw_serde]
 pub struct PairConfig {
@@ -2,7 +2,10 @@ use cosmwasm_schema::{cw_serde, QueryResponses};
 
 use crate::asset::{Asset, AssetInfo, PairInfo};
 
-use cosmwasm_std::{from_slice, Addr, Binary, Decimal, QuerierWrapper, StdResult, Uint128, Uint64};
+use cosmwasm_std::{
+    attr, from_slice, Addr, Attribute, Binary, Decimal, Decimal256, QuerierWrapper, StdResult,
+    Uint128, Uint64,
+};
 use cw20::Cw20ReceiveMsg;
 
 /// The default swap slippage
@@ -49,6 +52,10 @@ pub enum ExecuteMsg {
         belief_price: Option<Decimal>,
         max_spread: Option<Decimal>,
         to: Option<String>,
+        /// Refund native tokens sent above the offer amount instead of failing the swap.
+        /// Only the XYK pair supports it, other pairs reject swaps that set it.
+        #[serde(default)]
+        refund_excess: bool,
     },
     /// Update the pair configuration
     UpdateConfig { params: Binary },
@@ -104,12 +111,17 @@ pub enum QueryMsg {
     Simulation {
         offer_asset: Asset,
         ask_asset_info: Option<AssetInfo>,
+        /// The address that would execute the swap. Stableswap pairs quote no commission for
+        /// fee exempt swappers, other pairs charge every swapper the same.
+        sender: Option<String>,
     },
     /// Returns information about cumulative prices in a [`ReverseSimulationResponse`] object.
     #[returns(ReverseSimulationResponse)]
     ReverseSimulation {
         offer_asset_info: Option<AssetInfo>,
         ask_asset: Asset,
+        /// The address that would execute the swap, see [`QueryMsg::Simulation`]
+        sender: Option<String>,
     },
     /// Returns information about the cumulative prices in a [`CumulativePricesResponse`] object
     #[returns(CumulativePricesResponse)]
@@ -123,6 +135,23 @@ pub enum QueryMsg {
         asset_info: AssetInfo,
         block_height: Uint64,
     },
+    /// Returns the current pool invariant as a [`Decimal256`] value.
+    /// For XYK pools this is the product of the raw pool reserves (k = x * y).
+    /// For stableswap pools this is the D invariant computed with the current amplification
+    /// from the reserves normalized to their token decimals.
+    #[returns(Decimal256)]
+    Invariant {},
+    /// Returns the impermanent loss of a position entered at the specified price as a [`Decimal256`] fraction.
+    /// The entry price is the amount of the second pool asset paid per unit of the first one, in raw token units.
+    /// Only XYK pools support it.
+    #[returns(Decimal256)]
+    ImpermanentLoss { entry_price: Decimal },
+    /// Returns the total amount of swap fees accrued to liquidity providers for each asset in the pool
+    #[returns(Vec<Asset>)]
+    CumulativeFees {},
+    /// Returns the range of block heights for which [`QueryMsg::AssetBalanceAt`] returns tracked balances
+    #[returns(TrackedHeightRangeResponse)]
+    TrackedHeightRange {},
 }
 
 /// This struct is used to return a query result with the total amount of LP tokens and assets in a specific pool.
@@ -167,6 +196,29 @@ pub struct ReverseSimulationResponse {
     pub commission_amount: Uint128,
 }
 
+/// This structure holds the range of block heights with tracked asset balances.
+#[cw_serde]
+pub struct TrackedHeightRangeResponse {
+    /// The earliest block height with a tracked balance or None if tracking is disabled
+    pub earliest: Option<u64>,
+    /// The current block height (queries are valid up to this height)
+    pub latest: u64,
+}
+
+/// This structure is set as the data of a swap response, so contracts executing swaps
+/// through sub-messages can read the result without parsing attributes.
+#[cw_serde]
+pub struct SwapResponseData {
+    /// The amount of ask assets sent to the receiver
+    pub return_amount: Uint128,
+    /// The spread used in the swap operation
+    pub spread_amount: Uint128,
+    /// The amount of fees charged by the transaction
+    pub commission_amount: Uint128,
+    /// The amount of offer assets paid per one ask asset
+    pub execution_price: Decimal256,
+}
+
 /// This structure is used to return a cumulative prices query response.
 #[cw_serde]
 pub struct CumulativePricesResponse {
@@ -190,6 +242,9 @@ pub struct XYKPoolParams {
     /// They will not be tracked if the parameter is ignored.
     /// It can not be disabled later once enabled.
     pub track_asset_balances: Option<bool>,
+    /// The minimum commission charged per swap for each ask asset.
+    /// Swaps whose output can't cover it are rejected.
+    pub min_commission: Option<Vec<Asset>>,
 }
 
 /// This structure stores a XYK pool's configuration.
@@ -197,6 +252,9 @@ pub struct XYKPoolParams {
 pub struct XYKPoolConfig {
     /// Whether asset balances are tracked over blocks or not.
     pub track_asset_balances: bool,
+    /// The minimum commission charged per swap for each ask asset
+    #[serde(default)]
+    pub min_commission: Vec<Asset>,
 }
 
 /// This enum stores the option available to enable asset balances tracking over blocks.
@@ -204,6 +262,9 @@ pub struct XYKPoolConfig {
 pub enum XYKPoolUpdateParams {
     /// Enables asset balances tracking over blocks.
     EnableAssetBalancesTracking,
+    /// Replaces the minimum commission charged per swap for each ask asset.
+    /// Ask assets missing from the list are charged the regular commission only.
+    UpdateMinCommission { min_commission: Vec<Asset> },
 }
 
 /// This structure holds stableswap pool parameters.
@@ -220,13 +281,26 @@ pub struct StablePoolParams {
 pub struct StablePoolConfig {
     /// The stableswap pool amplification
     pub amp: Decimal,
+    /// Addresses that swap in the pool without paying commission
+    #[serde(default)]
+    pub fee_exempt: Vec<Addr>,
 }
 
-/// This enum stores the options available to start and stop changing a stableswap pool's amplification.
+/// This enum stores the options available to update a stableswap pool's parameters.
 #[cw_serde]
 pub enum StablePoolUpdateParams {
-    StartChangingAmp { next_amp: u64, next_amp_time: u64 },
+    StartChangingAmp {
+        next_amp: u64,
+        next_amp_time: u64,
+    },
     StopChangingAmp {},
+    /// Adds and removes addresses from the list of swappers that don't pay commission
+    UpdateFeeExempt {
+        #[serde(default)]
+        add: Vec<String>,
+        #[serde(default)]
+        remove: Vec<String>,
+    },
 }
 
 /// This function makes raw query to the factory contract and
@@ -244,11 +318,116 @@ pub fn migration_check(
     }
 }
 
+/// Returns the amount of offer assets paid per one returned ask asset.
+/// Zero is returned if the swap returned nothing.
+///
+/// The ratio of two [`Uint128`] values always fits into [`Decimal256`], so this never fails.
+pub fn execution_price(offer_amount: Uint128, return_amount: Uint128) -> Decimal256 {
+    if return_amount.is_zero() {
+        return Decimal256::zero();
+    }
+
+    Decimal256::from_ratio(offer_amount, return_amount)
+}
+
+/// Returns the attributes describing how a swap executed relative to the trader's belief price.
+/// Nothing is returned if no belief price was specified or the swap returned nothing.
+///
+/// The attributes are only informational, so building them never fails a swap.
+pub fn slippage_attributes(
+    belief_price: Option<Decimal>,
+    offer_amount: Uint128,
+    return_amount: Uint128,
+) -> Vec<Attribute> {
+    let belief_price = match belief_price {
+        Some(price) if !price.is_zero() && !return_amount.is_zero() => Decimal256::from(price),
+        _ => return vec![],
+    };
+
+    let execution_price = execution_price(offer_amount, return_amount);
+    let price_diff = if execution_price > belief_price {
+        execution_price - belief_price
+    } else {
+        belief_price - execution_price
+    };
+
+    let mut attributes = vec![
+        attr("belief_price", belief_price.to_string()),
+        attr("execution_price", execution_price.to_string()),
+    ];
+    // The deviation is skipped rather than failing the swap if it can't be represented
+    if let Ok(price_deviation) = price_diff.checked_div(belief_price) {
+        attributes.push(attr("price_deviation", price_deviation.to_string()));
+    }
+
+    attributes
+}
+
+/// Returns the impermanent loss of an XYK position as a fraction of the value it would have had
+/// outside the pool, `1 - 2 * sqrt(r) / (1 + r)` where `r` is the current price over the entry price.
+pub fn xyk_impermanent_loss(
+    entry_price: Decimal,
+    current_price: Decimal256,
+) -> StdResult<Decimal256> {
+    if entry_price.is_zero() {
+        return Err(StdError::generic_err("Entry price can not be zero"));
+    }
+
+    let price_ratio = current_price
+        .checked_div(Decimal256::from(entry_price))
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+    let pool_value = (Decimal256::from_ratio(2u8, 1u8) * price_ratio.sqrt())
+        .checked_div(Decimal256::one() + price_ratio)
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    // 2 * sqrt(r) never exceeds 1 + r, the check only guards against rounding
+    if pool_value >= Decimal256::one() {
+        Ok(Decimal256::zero())
+    } else {
+        Ok(Decimal256::one() - pool_value)
+    }
+}
+
+/// Splits a value expressed in units of the first pool asset into the amounts of both pool assets
+/// that match the current pool ratio, so providing them doesn't move the pool price.
+///
+/// * **value_in_base** the total value to provide, in units of the first asset in **reserves**.
+///
+/// * **reserves** the current pool reserves.
+pub fn optimal_provide_split(value_in_base: Uint128, reserves: &[Asset]) -> StdResult<Vec<Asset>> {
+    if reserves.len() != 2 {
+        return Err(StdError::generic_err(
+            "reserves must contain exactly two elements",
+        ));
+    }
+    if reserves.iter().any(|asset| asset.amount.is_zero()) {
+        return Err(StdError::generic_err("Pools are empty"));
+    }
+
+    // Half of the value stays in the base asset, the other half is converted at the pool price
+    let base_amount = value_in_base / Uint128::new(2);
+    let other_amount = (value_in_base - base_amount)
+        .checked_multiply_ratio(reserves[1].amount, reserves[0].amount)
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    Ok(vec![
+        Asset {
+            info: reserves[0].info.clone(),
+            amount: base_amount,
+        },
+        Asset {
+            info: reserves[1].info.clone(),
+            amount: other_amount,
+        },
+    ])
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::asset::native_asset_info;
+    use crate::asset::{native_asset_info, AssetInfoExt};
     use cosmwasm_std::{from_binary, to_binary};
+    use std::str::FromStr;
 
     #[cw_serde]
     pub struct LegacyInstantiateMsg {
@@ -288,6 +467,7 @@ mod tests {
             params: Some(
                 to_binary(&StablePoolConfig {
                     amp: Decimal::one(),
+                    fee_exempt: vec![],
                 })
                 .unwrap(),
             ),
@@ -302,4 +482,149 @@ mod tests {
         let variant: Cw20HookMsg = from_slice(br#"{"withdraw_liquidity": {} }"#).unwrap();
         assert_eq!(variant, Cw20HookMsg::WithdrawLiquidity { assets: vec![] });
     }
+
+    #[test]
+    fn slippage_attributes_without_belief_price() {
+        assert_eq!(
+            slippage_attributes(None, Uint128::new(100), Uint128::new(100)),
+            vec![]
+        );
+        assert_eq!(
+            slippage_attributes(Some(Decimal::one()), Uint128::new(100), Uint128::zero()),
+            vec![]
+        );
+
+        let attrs = slippage_attributes(
+            Some(Decimal::from_ratio(2u128, 1u128)),
+            Uint128::new(100),
+            Uint128::new(40),
+        );
+        assert_eq!(
+            attrs,
+            vec![
+                attr("belief_price", "2"),
+                attr("execution_price", "2.5"),
+                attr("price_deviation", "0.25"),
+            ]
+        );
+    }
+
+    #[test]
+    fn xyk_impermanent_loss_known_values() {
+        let il =
+            |entry: Decimal, current: Decimal256| xyk_impermanent_loss(entry, current).unwrap();
+
+        // The price didn't move
+        assert_eq!(il(Decimal::one(), Decimal256::one()), Decimal256::zero());
+        // 0.2 = 1 - 2 * sqrt(4) / (1 + 4), the same for the price falling to a quarter
+        assert_eq!(
+            il(Decimal::one(), Decimal256::from_ratio(4u8, 1u8)),
+            Decimal256::from_ratio(1u8, 5u8)
+        );
+        assert_eq!(
+            il(Decimal::from_ratio(4u8, 1u8), Decimal256::one()),
+            Decimal256::from_ratio(1u8, 5u8)
+        );
+        // A 2x price move loses about 5.72%
+        let loss = il(Decimal::percent(50), Decimal256::one());
+        assert!(loss > Decimal256::from_str("0.0571").unwrap());
+        assert!(loss < Decimal256::from_str("0.0572").unwrap());
+
+        assert_eq!(
+            xyk_impermanent_loss(Decimal::zero(), Decimal256::one()).unwrap_err(),
+            StdError::generic_err("Entry price can not be zero")
+        );
+    }
+
+    #[test]
+    fn optimal_provide_split_mints_more_than_a_naive_split() {
+        // A pool holding 1000 uusd and 4000 uluna, so one uusd is worth four uluna
+        let reserves = vec![
+            native_asset_info("uusd".to_string()).with_balance(1000u128),
+            native_asset_info("uluna".to_string()).with_balance(4000u128),
+        ];
+        let total_share = Uint128::new(2000u128);
+        // LP tokens an XYK pair mints for a deposit
+        let minted_share = |deposits: &[Asset]| {
+            std::cmp::min(
+                deposits[0]
+                    .amount
+                    .multiply_ratio(total_share, reserves[0].amount),
+                deposits[1]
+                    .amount
+                    .multiply_ratio(total_share, reserves[1].amount),
+            )
+        };
+
+        let split = optimal_provide_split(Uint128::new(100u128), &reserves).unwrap();
+        assert_eq!(
+            split,
+            vec![
+                native_asset_info("uusd".to_string()).with_balance(50u128),
+                native_asset_info("uluna".to_string()).with_balance(200u128),
+            ]
+        );
+        assert_eq!(minted_share(&split), Uint128::new(100u128));
+
+        // Splitting the same value one to one ignores the pool price and mints less
+        let naive_split = vec![
+            native_asset_info("uusd".to_string()).with_balance(50u128),
+            native_asset_info("uluna".to_string()).with_balance(50u128),
+        ];
+        assert_eq!(minted_share(&naive_split), Uint128::new(25u128));
+
+        assert_eq!(
+            optimal_provide_split(Uint128::new(100u128), &reserves[..1]).unwrap_err(),
+            StdError::generic_err("reserves must contain exactly two elements")
+        );
+        assert_eq!(
+            optimal_provide_split(
+                Uint128::new(100u128),
+                &[
+                    native_asset_info("uusd".to_string()).with_balance(0u128),
+                    native_asset_info("uluna".to_string()).with_balance(4000u128),
+                ]
+            )
+            .unwrap_err(),
+            StdError::generic_err("Pools are empty")
+        );
+    }
+
+    #[test]
+    fn execution_price_above_decimal_max() {
+        // 1e36 offer units per returned unit is above Decimal::MAX
+        let offer_amount = Uint128::new(10u128.pow(36));
+        let return_amount = Uint128::new(1);
+
+        assert_eq!(
+            execution_price(offer_amount, return_amount),
+            Decimal256::from_ratio(10u128.pow(36), 1u8)
+        );
+        assert_eq!(
+            execution_price(Uint128::MAX, Uint128::new(1)),
+            Decimal256::from_ratio(Uint128::MAX, 1u8)
+        );
+        assert_eq!(
+            execution_price(Uint128::new(100), Uint128::zero()),
+            Decimal256::zero()
+        );
+    }
+
+    #[test]
+    fn slippage_attributes_with_extreme_prices() {
+        // Execution price far above Decimal::MAX against the smallest belief price
+        let attrs = slippage_attributes(Some(Decimal::raw(1)), Uint128::MAX, Uint128::new(1));
+        assert_eq!(
+            attrs[..2],
+            [
+                attr("belief_price", "0.000000000000000001"),
+                attr("execution_price", Uint128::MAX.to_string()),
+            ]
+        );
+
+        // Belief price of Decimal::MAX against a tiny execution price
+        let attrs = slippage_attributes(Some(Decimal::MAX), Uint128::new(1), Uint128::MAX);
+        assert_eq!(attrs.len(), 3);
+        assert_eq!(attrs[0], attr("belief_price", Decimal::MAX.to_string()));
+    }
 }
@@ -100,6 +100,13 @@ pub enum QueryMsg {
     /// Query LP token virtual price
     #[returns(Decimal256)]
     LpPrice {},
+    /// Returns the current pool invariant D, same as [`QueryMsg::ComputeD`].
+    /// Matches the generic invariant query exposed by the other pair types.
+    #[returns(Decimal256)]
+    Invariant {},
+    /// Returns the total amount of swap fees accrued to liquidity providers for each asset in the pool
+    #[returns(Vec<Asset>)]
+    CumulativeFees {},
 }
 
 #[cw_serde]
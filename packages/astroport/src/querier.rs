@@ -204,6 +204,7 @@ pub fn simulate(
         &PairQueryMsg::Simulation {
             offer_asset: offer_asset.clone(),
             ask_asset_info: None,
+            sender: None,
         },
     )
 }
@@ -223,6 +224,7 @@ pub fn reverse_simulate(
         &PairQueryMsg::ReverseSimulation {
             offer_asset_info: None,
             ask_asset: ask_asset.clone(),
+            sender: None,
         },
     )
 }